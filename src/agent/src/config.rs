@@ -24,6 +24,7 @@ const PASSFD_LISTENER_PORT: &str = "agent.passfd_listener_port";
 const HOTPLUG_TIMOUT_OPTION: &str = "agent.hotplug_timeout";
 const DEBUG_CONSOLE_VPORT_OPTION: &str = "agent.debug_console_vport";
 const LOG_VPORT_OPTION: &str = "agent.log_vport";
+const TRACE_VPORT_OPTION: &str = "agent.trace_vport";
 const CONTAINER_PIPE_SIZE_OPTION: &str = "agent.container_pipe_size";
 const UNIFIED_CGROUP_HIERARCHY_OPTION: &str = "systemd.unified_cgroup_hierarchy";
 const CONFIG_FILE: &str = "agent.config_file";
@@ -96,6 +97,7 @@ pub struct AgentConfig {
     pub hotplug_timeout: time::Duration,
     pub debug_console_vport: i32,
     pub log_vport: i32,
+    pub trace_vport: i32,
     pub container_pipe_size: i32,
     pub server_addr: String,
     pub passfd_listener_port: i32,
@@ -116,6 +118,7 @@ pub struct AgentConfigBuilder {
     pub hotplug_timeout: Option<time::Duration>,
     pub debug_console_vport: Option<i32>,
     pub log_vport: Option<i32>,
+    pub trace_vport: Option<i32>,
     pub container_pipe_size: Option<i32>,
     pub server_addr: Option<String>,
     pub passfd_listener_port: Option<i32>,
@@ -180,6 +183,7 @@ impl Default for AgentConfig {
             hotplug_timeout: DEFAULT_HOTPLUG_TIMEOUT,
             debug_console_vport: 0,
             log_vport: 0,
+            trace_vport: 0,
             container_pipe_size: DEFAULT_CONTAINER_PIPE_SIZE,
             server_addr: format!("{}:{}", VSOCK_ADDR, DEFAULT_AGENT_VSOCK_PORT),
             passfd_listener_port: 0,
@@ -214,6 +218,7 @@ impl FromStr for AgentConfig {
         config_override!(agent_config_builder, agent_config, hotplug_timeout);
         config_override!(agent_config_builder, agent_config, debug_console_vport);
         config_override!(agent_config_builder, agent_config, log_vport);
+        config_override!(agent_config_builder, agent_config, trace_vport);
         config_override!(agent_config_builder, agent_config, container_pipe_size);
         config_override!(agent_config_builder, agent_config, server_addr);
         config_override!(agent_config_builder, agent_config, passfd_listener_port);
@@ -309,6 +314,13 @@ impl AgentConfig {
                 get_vsock_port,
                 |port| port > 0
             );
+            parse_cmdline_param!(
+                param,
+                TRACE_VPORT_OPTION,
+                config.trace_vport,
+                get_vsock_port,
+                |port| port > 0
+            );
             parse_cmdline_param!(
                 param,
                 PASSFD_LISTENER_PORT,
@@ -1704,4 +1716,32 @@ Caused by:
         // Verify that the default values are valid
         assert_eq!(config.hotplug_timeout, DEFAULT_HOTPLUG_TIMEOUT);
     }
+
+    #[test]
+    fn test_trace_vport() {
+        let dir = tempdir().expect("failed to create tmpdir");
+        let file_path = dir.path().join("cmdline");
+        let filename = file_path.to_str().expect("failed to create filename");
+
+        let mut file = File::create(filename).expect("failed to create file");
+        file.write_all(b"agent.trace_vport=10250")
+            .expect("failed to write file contents");
+
+        let config =
+            AgentConfig::from_cmdline(filename, vec![]).expect("failed to parse cmdline");
+        assert_eq!(config.trace_vport, 10250);
+
+        // Non positive ports are ignored and the exporter default is kept.
+        let mut file = File::create(filename).expect("failed to create file");
+        file.write_all(b"agent.trace_vport=0")
+            .expect("failed to write file contents");
+
+        let config =
+            AgentConfig::from_cmdline(filename, vec![]).expect("failed to parse cmdline");
+        assert_eq!(config.trace_vport, 0);
+
+        // The configuration file can override the port as well.
+        let config = AgentConfig::from_str("trace_vport = 10251").unwrap();
+        assert_eq!(config.trace_vport, 10251);
+    }
 }
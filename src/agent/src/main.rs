@@ -250,7 +250,7 @@ async fn real_main(init_mode: bool) -> std::result::Result<(), Box<dyn std::erro
     }
 
     if config.tracing {
-        tracer::setup_tracing(NAME, &logger)?;
+        tracer::setup_tracing(NAME, &logger, config.trace_vport)?;
     }
 
     let root_span = span!(tracing::Level::TRACE, "root-span");
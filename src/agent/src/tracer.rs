@@ -13,12 +13,17 @@ use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::Registry;
 use ttrpc::r#async::TtrpcContext;
 
-pub fn setup_tracing(name: &'static str, logger: &Logger) -> Result<()> {
+pub fn setup_tracing(name: &'static str, logger: &Logger, trace_vport: i32) -> Result<()> {
     let logger = logger.new(o!("subsystem" => "vsock-tracer"));
 
-    let exporter = vsock_exporter::Exporter::builder()
-        .with_logger(&logger)
-        .init();
+    let mut exporter_builder = vsock_exporter::Exporter::builder().with_logger(&logger);
+
+    // A zero port means the exporter default port is used.
+    if trace_vport > 0 {
+        exporter_builder = exporter_builder.with_port(trace_vport as u32);
+    }
+
+    let exporter = exporter_builder.init();
 
     let config = Config::default();
 
@@ -18,7 +18,8 @@ use async_trait::async_trait;
 use byteorder::{ByteOrder, NetworkEndian};
 use opentelemetry::sdk::export::trace::{ExportResult, SpanData, SpanExporter};
 use opentelemetry::sdk::export::ExportError;
-use slog::{error, info, o, Logger};
+use slog::{error, info, o, warn, Logger};
+use std::time::Duration;
 use std::io::ErrorKind;
 use std::sync::Arc;
 use thiserror::Error;
@@ -31,6 +32,20 @@ const ANY_CID: &str = "any";
 // Must match the value of the variable of the same name in the trace forwarder.
 const HEADER_SIZE_BYTES: u64 = std::mem::size_of::<u64>() as u64;
 
+// Magic number prefixed to every span batch ("KVSE" in network byte order),
+// so the forwarder can recognize the stream before deserializing anything.
+// Must match the value used by the trace forwarder.
+const BATCH_MAGIC: u32 = 0x4b56_5345;
+
+// Version of the serialized span layout. Bump this on incompatible `SpanData`
+// changes so that a forwarder built against a different layout can reject the
+// batch instead of silently mis-deserializing it.
+const BATCH_SCHEMA_VERSION: u16 = 1;
+
+// Size of the per-batch header: magic plus schema version.
+const BATCH_HEADER_SIZE_BYTES: usize =
+    std::mem::size_of::<u32>() + std::mem::size_of::<u16>();
+
 // By default, the VSOCK exporter should talk "out" to the host where the
 // forwarder is running.
 const DEFAULT_CID: u32 = libc::VMADDR_CID_HOST;
@@ -38,11 +53,29 @@ const DEFAULT_CID: u32 = libc::VMADDR_CID_HOST;
 // The VSOCK port the forwarders listens on by default
 const DEFAULT_PORT: u32 = 10240;
 
+// Maximum number of trace spans coalesced into a single vsock write by default.
+// Each span is still framed individually (header plus payload), so the forwarder
+// does not need to know about the batching.
+const DEFAULT_MAX_BATCH_SIZE: usize = 64;
+
+// Number of connection attempts made per export before giving up and
+// buffering the spans locally.
+const CONNECT_RETRIES: u32 = 3;
+
+// Initial delay between connection attempts, doubled after every failure.
+const CONNECT_RETRY_DELAY_MS: u64 = 10;
+
+// Upper bound on locally buffered spans while the forwarder is unavailable,
+// the oldest spans are dropped beyond this.
+const MAX_BACKLOG_SPANS: usize = 1024;
+
 #[derive(Debug)]
 pub struct Exporter {
     port: u32,
     cid: u32,
+    max_batch_size: usize,
     conn: Option<Arc<Mutex<VsockStream>>>,
+    backlog: Vec<SpanData>,
     logger: Logger,
 }
 
@@ -51,12 +84,56 @@ impl Exporter {
     pub fn builder() -> Builder {
         Builder::default()
     }
+
+    /// Number of spans buffered locally because the forwarder is unreachable.
+    pub fn backlog_depth(&self) -> usize {
+        self.backlog.len()
+    }
+
+    // Try to (re-)establish the connection to the forwarder, retrying with an
+    // exponential backoff so a briefly slow forwarder does not cost spans.
+    async fn reconnect(&mut self) -> Result<(), Error> {
+        let mut delay = Duration::from_millis(CONNECT_RETRY_DELAY_MS);
+        let mut last_error = Error::ConnectionError("no connection attempt made".into());
+
+        for _ in 0..CONNECT_RETRIES {
+            match connect_vsock(self.cid, self.port).await {
+                Ok(conn) => {
+                    self.conn = Some(Arc::new(Mutex::new(conn)));
+                    return Ok(());
+                }
+                Err(e) => last_error = e,
+            }
+
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+
+        Err(last_error)
+    }
+
+    /// Shut down the exporter, closing the connection to the forwarder.
+    ///
+    /// Unlike the synchronous `SpanExporter::shutdown()`, this performs an orderly shutdown of
+    /// the write side of the vsock connection so the forwarder sees EOF rather than a reset,
+    /// and waits for it to complete. The exporter is consumed: spans can no longer be exported
+    /// once shutdown has been requested.
+    pub async fn shutdown(mut self) -> Result<(), Error> {
+        if let Some(conn) = self.conn.take() {
+            let writer = conn.lock().await;
+            writer.shutdown(std::net::Shutdown::Write)?;
+        }
+
+        Ok(())
+    }
 }
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error("connection error: {0}")]
     ConnectionError(String),
+    #[error("invalid CID: {0}")]
+    InvalidCid(u32),
     #[error("serialisation error: {0}")]
     SerialisationError(#[from] bincode::Error),
     #[error("I/O error: {0}")]
@@ -73,36 +150,63 @@ fn make_io_error(desc: String) -> std::io::Error {
     std::io::Error::new(ErrorKind::Other, desc)
 }
 
-// Send a trace span to the forwarder running on the host.
-async fn write_span(
-    writer: Arc<Mutex<VsockStream>>,
-    span: &SpanData,
-) -> Result<(), std::io::Error> {
-    let mut writer = writer.lock().await;
+// Serialise a set of trace spans into a single buffer, framing each span with
+// its header so the forwarder can consume them one by one.
+// Encode a batch of spans for the wire. The format is:
+//
+//   magic:          u32, network endian (`BATCH_MAGIC`)
+//   schema version: u16, network endian (`BATCH_SCHEMA_VERSION`)
+//   per span:       payload length as u64, network endian, followed by the
+//                   bincode-encoded `SpanData` payload
+//
+// An empty batch encodes to nothing, no header is written.
+fn encode_batch(batch: &[SpanData]) -> Result<Vec<u8>, std::io::Error> {
+    let mut buf = Vec::new();
+
+    if batch.is_empty() {
+        return Ok(buf);
+    }
+
+    let mut magic_as_bytes = [0u8; std::mem::size_of::<u32>()];
+    NetworkEndian::write_u32(&mut magic_as_bytes, BATCH_MAGIC);
+    buf.extend_from_slice(&magic_as_bytes);
 
-    let encoded_payload: Vec<u8> =
-        bincode::serialize(&span).map_err(|e| make_io_error(e.to_string()))?;
+    let mut version_as_bytes = [0u8; std::mem::size_of::<u16>()];
+    NetworkEndian::write_u16(&mut version_as_bytes, BATCH_SCHEMA_VERSION);
+    buf.extend_from_slice(&version_as_bytes);
 
-    let payload_len: u64 = encoded_payload.len() as u64;
+    for span in batch {
+        let encoded_payload: Vec<u8> =
+            bincode::serialize(span).map_err(|e| make_io_error(e.to_string()))?;
 
-    let mut payload_len_as_bytes: [u8; HEADER_SIZE_BYTES as usize] =
-        [0; HEADER_SIZE_BYTES as usize];
+        let payload_len: u64 = encoded_payload.len() as u64;
 
-    // Encode the header
-    NetworkEndian::write_u64(&mut payload_len_as_bytes, payload_len);
+        let mut payload_len_as_bytes: [u8; HEADER_SIZE_BYTES as usize] =
+            [0; HEADER_SIZE_BYTES as usize];
 
-    // Send the header
-    writer.write_all(&payload_len_as_bytes).await?;
+        // Encode the header
+        NetworkEndian::write_u64(&mut payload_len_as_bytes, payload_len);
 
-    writer.write_all(&encoded_payload).await
+        buf.extend_from_slice(&payload_len_as_bytes);
+        buf.extend_from_slice(&encoded_payload);
+    }
+
+    Ok(buf)
 }
 
+// Send trace spans to the forwarder running on the host, coalescing up to
+// `max_batch_size` spans into a single vsock write to reduce syscall overhead.
 async fn handle_batch(
     writer: Arc<Mutex<VsockStream>>,
     batch: Vec<SpanData>,
+    max_batch_size: usize,
 ) -> Result<(), std::io::Error> {
-    for span_data in batch {
-        write_span(writer.clone(), &span_data).await?;
+    let mut writer = writer.lock().await;
+
+    for chunk in batch.chunks(std::cmp::max(max_batch_size, 1)) {
+        let encoded_chunk = encode_batch(chunk)?;
+
+        writer.write_all(&encoded_chunk).await?;
     }
 
     Ok(())
@@ -112,16 +216,36 @@ async fn handle_batch(
 impl SpanExporter for Exporter {
     async fn export(&mut self, batch: Vec<SpanData>) -> ExportResult {
         if self.conn.is_none() {
-            let conn = connect_vsock(self.cid, self.port).await.map(|e| {
+            if let Err(e) = self.reconnect().await {
                 error!(self.logger, "failed to obtain connection"; "error" => format!("{:?}", e));
-                e
-            })?;
 
-            self.conn = Some(Arc::new(Mutex::new(conn)));
+                // Keep the spans around for the next attempt, so a slow
+                // forwarder does not cost spans, and surface the stall to
+                // operators together with the backlog depth.
+                self.backlog.extend(batch);
+                if self.backlog.len() > MAX_BACKLOG_SPANS {
+                    let dropped = self.backlog.len() - MAX_BACKLOG_SPANS;
+                    self.backlog.drain(..dropped);
+                    warn!(self.logger, "span backlog full, dropping oldest spans";
+                        "dropped" => dropped);
+                }
+                warn!(self.logger, "trace forwarder unavailable, buffering spans";
+                    "backlog_depth" => self.backlog.len());
+
+                return Ok(());
+            }
         }
 
-        handle_batch(self.conn.as_ref().unwrap().clone(), batch)
-            .await
+        // Flush the backlog accumulated while disconnected ahead of the new
+        // batch to preserve span ordering.
+        let pending: Vec<SpanData> = self.backlog.drain(..).chain(batch).collect();
+
+        handle_batch(
+            self.conn.as_ref().unwrap().clone(),
+            pending,
+            self.max_batch_size,
+        )
+        .await
             .map_err(|e| {
                 error!(self.logger, "handle_batch error: {:?}", e);
                 if e.kind() == ErrorKind::NotConnected {
@@ -144,6 +268,7 @@ impl SpanExporter for Exporter {
 pub struct Builder {
     port: u32,
     cid: u32,
+    max_batch_size: usize,
     logger: Logger,
 }
 
@@ -154,6 +279,7 @@ impl Default for Builder {
         Builder {
             cid: DEFAULT_CID,
             port: DEFAULT_PORT,
+            max_batch_size: DEFAULT_MAX_BATCH_SIZE,
             logger,
         }
     }
@@ -168,6 +294,14 @@ impl Builder {
         Builder { port, ..self }
     }
 
+    /// Set the maximum number of spans coalesced into a single vsock write.
+    pub fn with_max_batch_size(self, max_batch_size: usize) -> Self {
+        Builder {
+            max_batch_size,
+            ..self
+        }
+    }
+
     pub fn with_logger(self, logger: &Logger) -> Self {
         Builder {
             logger: logger.new(o!()),
@@ -176,26 +310,172 @@ impl Builder {
     }
 
     pub fn init(self) -> Exporter {
-        let Builder { port, cid, logger } = self;
-
-        let cid_str: String = if self.cid == libc::VMADDR_CID_ANY {
+        let Builder {
+            port,
+            cid,
+            max_batch_size,
+            logger,
+        } = self;
+
+        let cid = normalize_cid(cid).unwrap_or_else(|e| {
+            warn!(
+                logger,
+                "replacing invalid CID with default";
+                "invalid-cid" => cid,
+                "default-cid" => DEFAULT_CID,
+                "error" => format!("{}", e),
+            );
+            DEFAULT_CID
+        });
+
+        let cid_str: String = if cid == libc::VMADDR_CID_ANY {
             ANY_CID.to_string()
         } else {
-            format!("{}", self.cid)
+            format!("{}", cid)
         };
 
         Exporter {
             port,
             cid,
+            max_batch_size,
             conn: None,
+            backlog: vec![],
             logger: logger.new(o!("cid" => cid_str, "port" => port)),
         }
     }
 }
 
+/// Validate a CID passed by the runtime before using it as a connection
+/// target. CID 0 (hypervisor) and CID 1 (reserved for local communication)
+/// can never carry forwarder traffic, `VMADDR_CID_ANY` and concrete CIDs are
+/// passed through unchanged.
+pub fn normalize_cid(cid: u32) -> Result<u32, Error> {
+    // libc does not expose VMADDR_CID_LOCAL on all versions, hence the literal.
+    if cid == libc::VMADDR_CID_HYPERVISOR || cid == 1 {
+        return Err(Error::InvalidCid(cid));
+    }
+
+    Ok(cid)
+}
+
 async fn connect_vsock(cid: u32, port: u32) -> Result<VsockStream, Error> {
     match VsockStream::connect(cid, port).await {
         Ok(conn) => Ok(conn),
         Err(e) => Err(Error::ConnectionError(e.to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use opentelemetry::sdk;
+    use opentelemetry::trace::{SpanContext, SpanId, SpanKind, StatusCode, TraceId, TraceState};
+
+    fn make_span(name: &'static str) -> SpanData {
+        let span_context = SpanContext::new(
+            TraceId::from_u128(7),
+            SpanId::from_u64(99),
+            0,
+            false,
+            TraceState::default(),
+        );
+
+        SpanData {
+            span_context,
+            parent_span_id: SpanId::from_u64(1),
+            span_kind: SpanKind::Client,
+            name: name.into(),
+            start_time: std::time::SystemTime::now(),
+            end_time: std::time::SystemTime::now(),
+            attributes: sdk::trace::EvictedHashMap::new(3, 0),
+            events: sdk::trace::EvictedQueue::new(3),
+            links: sdk::trace::EvictedQueue::new(3),
+            status_code: StatusCode::Ok,
+            status_message: "".into(),
+            resource: None,
+            instrumentation_lib: sdk::InstrumentationLibrary::new("", None),
+        }
+    }
+
+    #[test]
+    fn test_encode_batch_coalesces_spans() {
+        assert!(encode_batch(&[]).unwrap().is_empty());
+
+        let spans = vec![make_span("span-1"), make_span("span-2")];
+        let buf = encode_batch(&spans).unwrap();
+
+        // Walk the framed buffer: after the batch header each span is a
+        // header holding the payload length, followed by the payload itself.
+        let mut frames = 0;
+        let mut offset = BATCH_HEADER_SIZE_BYTES;
+        while offset < buf.len() {
+            let header = &buf[offset..offset + HEADER_SIZE_BYTES as usize];
+            let payload_len = NetworkEndian::read_u64(header) as usize;
+            offset += HEADER_SIZE_BYTES as usize + payload_len;
+            frames += 1;
+        }
+
+        assert_eq!(offset, buf.len());
+        assert_eq!(frames, spans.len());
+    }
+
+    #[test]
+    fn test_encode_batch_version_header() {
+        let buf = encode_batch(&[make_span("span-1")]).unwrap();
+
+        // The batch starts with the magic and the schema version, which a
+        // forwarder can parse back to reject layout mismatches.
+        assert!(buf.len() > BATCH_HEADER_SIZE_BYTES);
+        assert_eq!(NetworkEndian::read_u32(&buf[..4]), BATCH_MAGIC);
+        assert_eq!(NetworkEndian::read_u16(&buf[4..6]), BATCH_SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn test_normalize_cid() {
+        // reserved CIDs can never carry forwarder traffic
+        assert!(matches!(
+            normalize_cid(libc::VMADDR_CID_HYPERVISOR),
+            Err(Error::InvalidCid(0))
+        ));
+        assert!(matches!(normalize_cid(1), Err(Error::InvalidCid(1))));
+
+        // the host, wildcard, and concrete guest CIDs pass through unchanged
+        assert_eq!(
+            normalize_cid(libc::VMADDR_CID_HOST).unwrap(),
+            libc::VMADDR_CID_HOST
+        );
+        assert_eq!(
+            normalize_cid(libc::VMADDR_CID_ANY).unwrap(),
+            libc::VMADDR_CID_ANY
+        );
+        assert_eq!(normalize_cid(1024).unwrap(), 1024);
+    }
+
+    #[tokio::test]
+    async fn test_export_reports_backlog_when_disconnected() {
+        use opentelemetry::sdk::export::trace::SpanExporter;
+
+        // A port nothing listens on: every connection attempt fails.
+        let mut exporter = Exporter::builder().with_port(1).init();
+        assert_eq!(exporter.backlog_depth(), 0);
+
+        // The spans are buffered rather than dropped, and the backlog depth
+        // is observable.
+        exporter.export(vec![make_span("span-1")]).await.unwrap();
+        assert_eq!(exporter.backlog_depth(), 1);
+
+        exporter
+            .export(vec![make_span("span-2"), make_span("span-3")])
+            .await
+            .unwrap();
+        assert_eq!(exporter.backlog_depth(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_without_connection() {
+        // An exporter which never connected must still shut down cleanly.
+        let exporter = Exporter::builder().init();
+
+        exporter.shutdown().await.unwrap();
+    }
+}
@@ -18,13 +18,14 @@
 use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
 
 use dbs_address_space::{
     AddressSpace, AddressSpaceError, AddressSpaceLayout, AddressSpaceRegion,
-    AddressSpaceRegionType, NumaNode, NumaNodeInfo, MPOL_MF_MOVE, MPOL_PREFERRED,
+    AddressSpaceRegionType, MemorySourceType, NumaNode, NumaNodeInfo, MPOL_MF_MOVE, MPOL_PREFERRED,
 };
 use dbs_allocator::Constraint;
 use kvm_bindings::kvm_userspace_memory_region;
@@ -80,6 +81,11 @@ pub enum AddressManagerError {
     #[error("invalid address space region (0x{0:x}, 0x{1:x})")]
     InvalidAddressRange(u64, GuestUsize),
 
+    /// Device MMIO regions may not be backed by memory files, so they can not
+    /// be mmapped, e.g. pass-through BAR regions.
+    #[error("device MMIO region can not be mmapped")]
+    CannotMmapDeviceMemory,
+
     /// No available mem address.
     #[error("no available mem address")]
     NoAvailableMemAddress,
@@ -265,6 +271,15 @@ impl AddressSpaceMgr {
         numa_region_infos: &[NumaRegionInfo],
         mut param: AddressSpaceMgrBuilder,
     ) -> Result<()> {
+        // A hugetlbfs backed region maps a file on the host, so an empty
+        // `mem_file_path` would silently turn into a bogus file name once
+        // the region index suffix gets appended.
+        let source_type = MemorySourceType::from_str(param.mem_type)
+            .map_err(|_e| AddressManagerError::TypeInvalid(param.mem_type.to_string()))?;
+        if source_type == MemorySourceType::FileOnHugeTlbFs && param.mem_file.is_empty() {
+            return Err(AddressManagerError::FileInvalid(param.mem_file.to_string()));
+        }
+
         let mut regions = Vec::new();
         let mut start_addr = dbs_boot::layout::GUEST_MEM_START;
 
@@ -445,7 +460,7 @@ impl AddressSpaceMgr {
         }
         // The device MMIO regions may not be backed by memory files, so refuse to mmap them.
         if region.region_type() == AddressSpaceRegionType::DeviceMemory {
-            return Err(AddressManagerError::InvalidOperation);
+            return Err(AddressManagerError::CannotMmapDeviceMemory);
         }
 
         // The GuestRegionMmap/MmapRegion will take ownership of the FileOffset object,
@@ -632,11 +647,80 @@ impl AddressSpaceMgr {
         self.base_to_slot.clone()
     }
 
+    /// Remove the guest memory region previously mapped at `guest_addr`, as
+    /// needed when hot-removing memory.
+    ///
+    /// This deletes the region's KVM memory slot by issuing a zero-sized
+    /// `set_user_memory_region`, releases the slot back to the resource
+    /// manager and drops the base to slot mapping. The caller is responsible
+    /// for updating its guest memory view.
+    pub fn remove_region(
+        &mut self,
+        res_mgr: &ResourceManager,
+        vmfd: Option<&VmFd>,
+        guest_addr: GuestAddress,
+    ) -> Result<()> {
+        let slot = self
+            .base_to_slot
+            .lock()
+            .unwrap()
+            .remove(&guest_addr.raw_value())
+            .ok_or(AddressManagerError::InvalidOperation)?;
+
+        if let Some(vmfd) = vmfd {
+            let mem_region = kvm_userspace_memory_region {
+                slot,
+                guest_phys_addr: guest_addr.raw_value(),
+                memory_size: 0,
+                userspace_addr: 0,
+                flags: 0,
+            };
+            // Safe because a zero-sized memory region deletes the slot.
+            unsafe { vmfd.set_user_memory_region(mem_region) }
+                .map_err(AddressManagerError::KvmSetMemorySlot)?;
+        }
+
+        res_mgr
+            .free_kvm_mem_slot(slot)
+            .map_err(|_e| AddressManagerError::InvalidOperation)?;
+
+        Ok(())
+    }
+
+    /// Get the total usable guest RAM in bytes, summing up all regions of
+    /// type `DefaultMemory`. Useful for reporting and for balloon/virtio-mem
+    /// sizing decisions.
+    pub fn total_guest_mem_bytes(&self) -> Result<u64> {
+        let address_space = self
+            .address_space
+            .as_ref()
+            .ok_or(AddressManagerError::InvalidOperation)?;
+
+        let mut total = 0u64;
+        let _ = address_space.walk_regions(|region| {
+            if region.region_type() == AddressSpaceRegionType::DefaultMemory {
+                total += region.len();
+            }
+            Ok(())
+        });
+
+        Ok(total)
+    }
+
     /// get numa nodes infos from address space manager.
     pub fn get_numa_nodes(&self) -> &BTreeMap<u32, NumaNode> {
         &self.numa_nodes
     }
 
+    /// Find the guest numa node a vcpu belongs to, `None` if the vcpu id is
+    /// not listed in any node.
+    pub fn numa_node_of_vcpu(&self, vcpu_id: u32) -> Option<u32> {
+        self.numa_nodes
+            .iter()
+            .find(|(_id, node)| node.vcpu_ids().contains(&vcpu_id))
+            .map(|(id, _node)| *id)
+    }
+
     /// add cpu and memory numa informations to BtreeMap
     fn insert_into_numa_nodes(
         &mut self,
@@ -664,14 +748,34 @@ impl AddressSpaceMgr {
     ///
     /// Force all working threads to exit if `stop` is true.
     pub fn wait_prealloc(&mut self, stop: bool) -> Result<()> {
+        self.wait_prealloc_with_progress(stop, None)
+    }
+
+    /// Wait for the pre-allocation working threads to finish work, reporting
+    /// progress along the way.
+    ///
+    /// Force all working threads to exit if `stop` is true. The `progress`
+    /// callback is invoked with the number of joined threads each time one
+    /// finishes, so long preallocations of large hugepage guests do not
+    /// appear hung.
+    pub fn wait_prealloc_with_progress(
+        &mut self,
+        stop: bool,
+        mut progress: Option<&mut dyn FnMut(usize)>,
+    ) -> Result<()> {
         if stop {
             self.prealloc_exit.store(true, Ordering::Release);
         }
+        let mut completed = 0;
         while let Some(handlers) = self.prealloc_handlers.pop() {
             if let Err(e) = handlers.join() {
                 error!("wait_prealloc join fail {:?}", e);
                 return Err(AddressManagerError::JoinFail);
             }
+            completed += 1;
+            if let Some(cb) = progress.as_mut() {
+                cb(completed);
+            }
         }
         Ok(())
     }
@@ -691,6 +795,20 @@ impl Default for AddressSpaceMgr {
     }
 }
 
+impl Drop for AddressSpaceMgr {
+    /// Ask the pre-allocation working threads to exit and wait for them, so
+    /// they do not keep touching guest memory which may get unmapped once the
+    /// manager is gone.
+    fn drop(&mut self) {
+        self.prealloc_exit.store(true, Ordering::Release);
+        while let Some(handler) = self.prealloc_handlers.pop() {
+            if let Err(e) = handler.join() {
+                error!("wait_prealloc join fail {:?}", e);
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use dbs_boot::layout::GUEST_MEM_START;
@@ -830,6 +948,184 @@ mod tests {
         assert_eq!(*as_mgr.get_numa_nodes().get(&0).unwrap(), numa_node);
     }
 
+    #[test]
+    fn test_total_guest_mem_bytes() {
+        // an uninitialized manager cannot report a size
+        let as_mgr = AddressSpaceMgr::default();
+        assert!(matches!(
+            as_mgr.total_guest_mem_bytes(),
+            Err(AddressManagerError::InvalidOperation)
+        ));
+
+        // two regions must be summed up
+        let res_mgr = ResourceManager::new(None);
+        let numa_region_infos = vec![
+            NumaRegionInfo {
+                size: 16,
+                host_numa_node_id: None,
+                guest_numa_node_id: Some(0),
+                vcpu_ids: vec![0],
+            },
+            NumaRegionInfo {
+                size: 32,
+                host_numa_node_id: None,
+                guest_numa_node_id: Some(0),
+                vcpu_ids: vec![1],
+            },
+        ];
+        let builder = AddressSpaceMgrBuilder::new("shmem", "").unwrap();
+        let as_mgr = builder.build(&res_mgr, &numa_region_infos).unwrap();
+        assert_eq!(as_mgr.total_guest_mem_bytes().unwrap(), (16 + 32) << 20);
+    }
+
+    #[test]
+    fn test_create_mmap_region_device_memory() {
+        // device MMIO regions are not backed by memory files and must be
+        // reported with the dedicated variant so callers can special-case
+        // pass-through BAR regions
+        let mut as_mgr = AddressSpaceMgr::default();
+        let region = Arc::new(AddressSpaceRegion::new(
+            AddressSpaceRegionType::DeviceMemory,
+            GuestAddress(GUEST_MEM_START),
+            0x10_0000,
+        ));
+        let res = as_mgr.create_mmap_region(region);
+        assert!(matches!(
+            res,
+            Err(AddressManagerError::CannotMmapDeviceMemory)
+        ));
+    }
+
+    #[test]
+    fn test_remove_region() {
+        let res_mgr = ResourceManager::new(None);
+        let numa_region_infos = vec![NumaRegionInfo {
+            size: 2,
+            host_numa_node_id: None,
+            guest_numa_node_id: Some(0),
+            vcpu_ids: vec![1, 2],
+        }];
+        let builder = AddressSpaceMgrBuilder::new("shmem", "").unwrap();
+        let mut as_mgr = builder.build(&res_mgr, &numa_region_infos).unwrap();
+
+        let base = GUEST_MEM_START;
+        let slot = *as_mgr
+            .get_base_to_slot_map()
+            .lock()
+            .unwrap()
+            .get(&base)
+            .unwrap();
+
+        assert!(as_mgr
+            .remove_region(&res_mgr, None, GuestAddress(base))
+            .is_ok());
+
+        // the mapping is gone and the kvm slot can be allocated again
+        assert!(!as_mgr
+            .get_base_to_slot_map()
+            .lock()
+            .unwrap()
+            .contains_key(&base));
+        assert_eq!(res_mgr.allocate_kvm_mem_slot(1, Some(slot)), Some(slot));
+
+        // removing an unknown region must fail
+        let res = as_mgr.remove_region(&res_mgr, None, GuestAddress(base));
+        assert!(matches!(res, Err(AddressManagerError::InvalidOperation)));
+    }
+
+    #[test]
+    fn test_numa_node_of_vcpu() {
+        let res_mgr = ResourceManager::new(None);
+        let numa_region_infos = vec![
+            NumaRegionInfo {
+                size: 2,
+                host_numa_node_id: None,
+                guest_numa_node_id: Some(0),
+                vcpu_ids: vec![0, 1],
+            },
+            NumaRegionInfo {
+                size: 2,
+                host_numa_node_id: None,
+                guest_numa_node_id: Some(1),
+                vcpu_ids: vec![2, 3],
+            },
+        ];
+        let builder = AddressSpaceMgrBuilder::new("shmem", "").unwrap();
+        let as_mgr = builder.build(&res_mgr, &numa_region_infos).unwrap();
+
+        assert_eq!(as_mgr.numa_node_of_vcpu(0), Some(0));
+        assert_eq!(as_mgr.numa_node_of_vcpu(1), Some(0));
+        assert_eq!(as_mgr.numa_node_of_vcpu(2), Some(1));
+        assert_eq!(as_mgr.numa_node_of_vcpu(3), Some(1));
+        assert_eq!(as_mgr.numa_node_of_vcpu(4), None);
+    }
+
+    #[test]
+    fn test_wait_prealloc_progress_callback() {
+        let res_mgr = ResourceManager::new(None);
+        let numa_region_infos = vec![
+            NumaRegionInfo {
+                size: 2,
+                host_numa_node_id: None,
+                guest_numa_node_id: Some(0),
+                vcpu_ids: vec![1],
+            },
+            NumaRegionInfo {
+                size: 2,
+                host_numa_node_id: None,
+                guest_numa_node_id: Some(0),
+                vcpu_ids: vec![2],
+            },
+        ];
+        let mut builder = AddressSpaceMgrBuilder::new("hugeshmem", "").unwrap();
+        builder.toggle_prealloc(true);
+        let mut as_mgr = builder.build(&res_mgr, &numa_region_infos).unwrap();
+
+        // the callback must fire once per joined prealloc thread with a
+        // monotonically increasing completion count
+        let expected = as_mgr.prealloc_handlers.len();
+        let mut reported = Vec::new();
+        as_mgr
+            .wait_prealloc_with_progress(false, Some(&mut |done| reported.push(done)))
+            .unwrap();
+        assert_eq!(reported, (1..=expected).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn test_drop_mid_prealloc() {
+        let res_mgr = ResourceManager::new(None);
+        let numa_region_infos = vec![NumaRegionInfo {
+            size: 16,
+            host_numa_node_id: None,
+            guest_numa_node_id: Some(0),
+            vcpu_ids: vec![1, 2],
+        }];
+        let mut builder = AddressSpaceMgrBuilder::new("hugeshmem", "").unwrap();
+        builder.toggle_prealloc(true);
+        let as_mgr = builder.build(&res_mgr, &numa_region_infos).unwrap();
+
+        // dropping the manager without calling wait_prealloc() must stop and
+        // join the prealloc threads instead of leaving them running
+        drop(as_mgr);
+    }
+
+    #[test]
+    fn test_create_address_space_empty_file_backed_path() {
+        let res_mgr = ResourceManager::new(None);
+        let numa_region_infos = vec![NumaRegionInfo {
+            size: 2,
+            host_numa_node_id: None,
+            guest_numa_node_id: Some(0),
+            vcpu_ids: vec![1, 2],
+        }];
+
+        // a file backed memory type without a backing file path must be
+        // rejected up front instead of creating a bogus file
+        let builder = AddressSpaceMgrBuilder::new("hugetlbfs", "").unwrap();
+        let res = builder.build(&res_mgr, &numa_region_infos);
+        assert!(matches!(res, Err(AddressManagerError::FileInvalid(_))));
+    }
+
     #[test]
     fn test_address_space_mgr_async_prealloc() {
         let res_mgr = ResourceManager::new(None);
@@ -17,6 +17,8 @@ use crate::error::{Result, StartMicroVmError, StopMicrovmError};
 use crate::event_manager::EventManager;
 use crate::tracer::{DragonballTracer, TraceError, TraceInfo};
 use crate::vcpu::VcpuManagerError;
+#[cfg(any(feature = "virtio-mem", feature = "virtio-balloon", test))]
+use crate::vm::Vm;
 use crate::vm::{CpuTopology, KernelConfigInfo, VmConfigInfo};
 use crate::vmm::Vmm;
 
@@ -160,6 +162,11 @@ pub enum VmmActionError {
     /// The action 'RemoveHostDevice' failed because of vcpu manager internal error.
     #[error("remove host device error: {0}")]
     RemoveHostDevice(#[source] VcpuManagerError),
+
+    #[cfg(any(feature = "virtio-mem", feature = "virtio-balloon", test))]
+    /// Revalidating the vcpu IoManager cache after a device hot-add failed.
+    #[error("failed to revalidate vcpu IoManager cache: {0}")]
+    RevalidateVcpuIoCache(#[source] VcpuManagerError),
 }
 
 /// This enum represents the public interface of the VMM. Each action contains various
@@ -991,11 +998,20 @@ impl VmmService {
                 }
             })?;
 
+        let hotplug = vm.is_vm_initialized();
+        if hotplug {
+            validate_guest_numa_node_id(vm, config.guest_numa_node_id)?;
+        }
         vm.device_manager_mut()
             .mem_manager
             .insert_or_update_device(ctx, config)
-            .map(|_| VmmData::Empty)
-            .map_err(VmmActionError::Mem)
+            .map_err(VmmActionError::Mem)?;
+
+        if hotplug {
+            revalidate_vcpus_io_cache(vm)?;
+        }
+
+        Ok(VmmData::Empty)
     }
 
     #[cfg(feature = "virtio-balloon")]
@@ -1022,12 +1038,54 @@ impl VmmService {
                 }
             })?;
 
+        let hotplug = vm.is_vm_initialized();
         vm.device_manager_mut()
             .balloon_manager
             .insert_or_update_device(ctx, config)
-            .map(|_| VmmData::Empty)
-            .map_err(VmmActionError::Balloon)
+            .map_err(VmmActionError::Balloon)?;
+
+        if hotplug {
+            revalidate_vcpus_io_cache(vm)?;
+        }
+
+        Ok(VmmData::Empty)
+    }
+}
+
+/// Refresh the IoManager cache of all vcpus.
+///
+/// A hot-added MMIO device is only visible to a vcpu once the vcpu's cached
+/// IoManager has been refreshed, so device hot-add paths must call this right
+/// after the device has been inserted.
+#[cfg(any(feature = "virtio-mem", feature = "virtio-balloon", test))]
+fn revalidate_vcpus_io_cache(vm: &Vm) -> std::result::Result<(), VmmActionError> {
+    vm.vcpu_manager()
+        .map_err(VmmActionError::RevalidateVcpuIoCache)?
+        .revalidate_all_vcpus_cache()
+        .map_err(VmmActionError::RevalidateVcpuIoCache)
+}
+
+/// Validate the guest NUMA node id of a hot-added virtio-mem device against
+/// the NUMA topology known to the address space manager, so that a hot-add
+/// targeting a nonexistent guest node fails instead of silently landing on
+/// node 0.
+#[cfg(feature = "virtio-mem")]
+fn validate_guest_numa_node_id(
+    vm: &Vm,
+    guest_numa_node_id: Option<u16>,
+) -> std::result::Result<(), VmmActionError> {
+    if let Some(node_id) = guest_numa_node_id {
+        if !vm
+            .address_space_mgr()
+            .get_numa_nodes()
+            .contains_key(&(node_id as u32))
+        {
+            return Err(VmmActionError::Mem(MemDeviceError::InvalidGuestNumaNodeId(
+                node_id,
+            )));
+        }
     }
+    Ok(())
 }
 
 fn handle_cpu_topology(
@@ -1067,6 +1125,8 @@ mod tests {
     use test_utils::skip_if_not_root;
     use vmm_sys_util::tempfile::TempFile;
 
+    use seccompiler::BpfProgram;
+
     use super::*;
     use crate::vmm::tests::create_vmm_instance;
 
@@ -1916,4 +1976,52 @@ mod tests {
             t.check_request();
         }
     }
+
+    #[test]
+    fn test_revalidate_vcpus_io_cache() {
+        skip_if_not_root!();
+
+        let epoll_mgr = EpollManager::default();
+        let mut vmm = create_vmm_instance(epoll_mgr);
+        let vm = vmm.get_vm_mut().unwrap();
+
+        // without a vcpu manager the revalidation must fail loudly
+        assert!(matches!(
+            revalidate_vcpus_io_cache(vm),
+            Err(VmmActionError::RevalidateVcpuIoCache(_))
+        ));
+
+        vm.set_vm_config(VmConfigInfo::default());
+        vm.init_guest_memory().unwrap();
+        vm.init_vcpu_manager(vm.vm_as().unwrap().clone(), BpfProgram::default())
+            .unwrap();
+
+        // the hot-add paths go through revalidate_vcpus_io_cache(), so a
+        // bumped counter means a hot-add revalidates the vcpu caches
+        revalidate_vcpus_io_cache(vm).unwrap();
+        assert_eq!(vm.vcpu_manager().unwrap().revalidate_cache_count, 1);
+    }
+
+    #[cfg(feature = "virtio-mem")]
+    #[test]
+    fn test_validate_guest_numa_node_id() {
+        skip_if_not_root!();
+
+        let epoll_mgr = EpollManager::default();
+        let mut vmm = create_vmm_instance(epoll_mgr);
+        let vm = vmm.get_vm_mut().unwrap();
+
+        vm.set_vm_config(VmConfigInfo::default());
+        vm.init_guest_memory().unwrap();
+
+        // the default memory layout only knows guest NUMA node 0
+        validate_guest_numa_node_id(vm, None).unwrap();
+        validate_guest_numa_node_id(vm, Some(0)).unwrap();
+        assert!(matches!(
+            validate_guest_numa_node_id(vm, Some(5)),
+            Err(VmmActionError::Mem(MemDeviceError::InvalidGuestNumaNodeId(
+                5
+            )))
+        ));
+    }
 }
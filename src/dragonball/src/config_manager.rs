@@ -182,6 +182,19 @@ where
     pub fn set_device(&mut self, device: Arc<dyn DeviceIo>) {
         self.device = Some(device);
     }
+
+    /// Get the device object associated with the configuration.
+    pub fn device(&self) -> Option<&Arc<dyn DeviceIo>> {
+        self.device.as_ref()
+    }
+
+    /// Get the device object associated with the configuration, downcast to
+    /// the concrete device type `D`.
+    pub fn downcast_device<D: DeviceIo + 'static>(&self) -> Option<&D> {
+        self.device
+            .as_ref()
+            .and_then(|device| device.as_any().downcast_ref::<D>())
+    }
 }
 
 impl<T> Clone for DeviceConfigInfo<T>
@@ -763,6 +776,38 @@ mod tests {
         assert_eq!(configs.len(), 0);
     }
 
+    #[test]
+    fn test_device_config_info_downcast_device() {
+        struct DummyDevice {}
+
+        impl DeviceIo for DummyDevice {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+
+        struct OtherDevice {}
+
+        impl DeviceIo for OtherDevice {
+            fn as_any(&self) -> &dyn std::any::Any {
+                self
+            }
+        }
+
+        let mut info = DeviceConfigInfo::new(DummyConfigInfo {
+            id: "1".to_owned(),
+            content: "a".to_owned(),
+        });
+        assert!(info.device().is_none());
+        assert!(info.downcast_device::<DummyDevice>().is_none());
+
+        info.set_device(Arc::new(DummyDevice {}));
+        assert!(info.device().is_some());
+        assert!(info.downcast_device::<DummyDevice>().is_some());
+        // downcasting to the wrong concrete type yields None
+        assert!(info.downcast_device::<OtherDevice>().is_none());
+    }
+
     #[test]
     fn test_rate_limiter_configs() {
         const SIZE: u64 = 1024 * 1024;
@@ -22,7 +22,9 @@ mod layout;
 pub use layout::{AddressSpaceLayout, USABLE_END};
 
 mod memory;
-pub use memory::{GuestMemoryHybrid, GuestMemoryManager, GuestRegionHybrid, GuestRegionRaw};
+pub use memory::{
+    GuestMemoryHybrid, GuestMemoryManager, GuestRegionHybrid, GuestRegionRaw, MemorySourceType,
+};
 
 mod numa;
 pub use self::numa::{NumaIdTable, NumaNode, NumaNodeInfo, MPOL_MF_MOVE, MPOL_PREFERRED};
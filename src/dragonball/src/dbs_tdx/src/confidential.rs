@@ -0,0 +1,100 @@
+// Copyright (C) 2023 Alibaba Cloud. All rights reserved.
+// SPDX-License-Identifier: Apache-2.0
+
+//! Generic lifecycle of a confidential guest.
+//!
+//! Every TEE follows the same high level boot flow: initialize the TEE
+//! context of the VM, load the firmware and data regions that have to be
+//! measured into the initial guest image, then finalize the measurement
+//! before the first vcpu runs. The [`ConfidentialVm`] trait captures that
+//! flow so VMMs can drive any TEE through one interface; [`TdxVm`]
+//! implements it on top of the TDX module and an SEV implementation can
+//! plug in later.
+
+use std::os::unix::io::RawFd;
+
+use kvm_bindings::CpuId;
+
+use crate::tdx_ioctls::{tdx_finalize, tdx_init, tdx_init_memory_region, TdxIoctlError};
+
+/// Common lifecycle of a confidential guest.
+///
+/// The methods must be called in order: [`ConfidentialVm::init`] once,
+/// [`ConfidentialVm::load_firmware`] once per measured region, then
+/// [`ConfidentialVm::finalize`] once. No region may be loaded after the
+/// measurement has been finalized.
+pub trait ConfidentialVm {
+    /// TEE specific error type.
+    type Error;
+
+    /// Initialize the TEE context of the VM.
+    fn init(&mut self) -> Result<(), Self::Error>;
+
+    /// Load a firmware or data region into guest memory, measuring it into
+    /// the initial guest image when `measure` is true.
+    fn load_firmware(
+        &mut self,
+        host_address: u64,
+        guest_address: u64,
+        size: u64,
+        measure: bool,
+    ) -> Result<(), Self::Error>;
+
+    /// Finalize the measurement of the initial guest image.
+    fn finalize(&mut self) -> Result<(), Self::Error>;
+}
+
+/// A TDX guest driven through the `KVM_MEMORY_ENCRYPT_OP` ioctls.
+pub struct TdxVm {
+    vm_fd: RawFd,
+    cpuid: CpuId,
+    max_vcpus: u32,
+}
+
+impl TdxVm {
+    /// Create a TDX guest handle for the VM behind `vm_fd`.
+    pub fn new(vm_fd: RawFd, cpuid: CpuId, max_vcpus: u32) -> Self {
+        TdxVm {
+            vm_fd,
+            cpuid,
+            max_vcpus,
+        }
+    }
+}
+
+impl ConfidentialVm for TdxVm {
+    type Error = TdxIoctlError;
+
+    fn init(&mut self) -> Result<(), Self::Error> {
+        tdx_init(&self.vm_fd, &self.cpuid, self.max_vcpus)
+    }
+
+    fn load_firmware(
+        &mut self,
+        host_address: u64,
+        guest_address: u64,
+        size: u64,
+        measure: bool,
+    ) -> Result<(), Self::Error> {
+        tdx_init_memory_region(&self.vm_fd, host_address, guest_address, size, measure)
+    }
+
+    fn finalize(&mut self) -> Result<(), Self::Error> {
+        tdx_finalize(&self.vm_fd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_confidential_vm<T: ConfidentialVm>(_vm: &T) {}
+
+    #[test]
+    fn test_tdx_vm_is_a_confidential_vm() {
+        // driving the TDX module needs real hardware, so only check that the
+        // TDX implementation satisfies the generic trait
+        let vm = TdxVm::new(-1, CpuId::new(0).unwrap(), 1);
+        assert_confidential_vm(&vm);
+    }
+}
@@ -1,5 +1,7 @@
 // Copyright (C) 2023 Alibaba Cloud. All rights reserved.
 // SPDX-License-Identifier: Apache-2.0
 
+#[cfg(target_arch = "x86_64")]
+pub mod confidential;
 #[cfg(target_arch = "x86_64")]
 pub mod tdx_ioctls;
@@ -85,6 +85,9 @@ pub enum TdxIoctlError {
     /// Failed to init TDX memory region.
     #[error("Failed to init TDX memory region: {0}")]
     TdxInitMemRegion(#[source] std::io::Error),
+    /// Invalid TDX memory region layout.
+    #[error("Invalid TDX memory region layout: {0}")]
+    TdxInvalidMemRegion(String),
     /// Failed to init TDX vcpu.
     #[error("Failed to init TDX vcpu: {0}")]
     TdxInitVcpu(#[source] std::io::Error),
@@ -200,6 +203,72 @@ pub fn tdx_init_memory_region(
     .map_err(TdxIoctlError::TdxInitMemRegion)
 }
 
+/// A guest physical memory region to be handed to [`tdx_init_memory_region`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct TdxMemoryRegion {
+    /// Guest physical address of the region.
+    pub guest_address: u64,
+    /// Size of the region in bytes.
+    pub size: u64,
+}
+
+/// Validate the layout of the memory regions about to be handed to
+/// [`tdx_init_memory_region`].
+///
+/// Every region must be non-empty, page aligned, must not wrap around the
+/// address space and must end at or below `mem_end`, and no two regions may
+/// overlap. Regions violating these rules would corrupt the TD measurement,
+/// so callers should reject them before touching the TDX module.
+pub fn tdx_validate_memory_regions(
+    regions: &[TdxMemoryRegion],
+    mem_end: u64,
+) -> std::result::Result<(), TdxIoctlError> {
+    const PAGE_SIZE: u64 = 4096;
+
+    // bounds-check every region before the overlap pass, so the address
+    // arithmetic below can never overflow
+    for region in regions {
+        if region.size == 0 {
+            return Err(TdxIoctlError::TdxInvalidMemRegion(format!(
+                "region at {:#x} is empty",
+                region.guest_address
+            )));
+        }
+        if region.guest_address % PAGE_SIZE != 0 || region.size % PAGE_SIZE != 0 {
+            return Err(TdxIoctlError::TdxInvalidMemRegion(format!(
+                "region at {:#x} with size {:#x} is not page aligned",
+                region.guest_address, region.size
+            )));
+        }
+        if region
+            .guest_address
+            .checked_add(region.size)
+            .filter(|e| *e <= mem_end)
+            .is_none()
+        {
+            return Err(TdxIoctlError::TdxInvalidMemRegion(format!(
+                "region at {:#x} with size {:#x} exceeds guest memory end {:#x}",
+                region.guest_address, region.size, mem_end
+            )));
+        }
+    }
+
+    for (i, region) in regions.iter().enumerate() {
+        for other in &regions[i + 1..] {
+            if region.guest_address < other.guest_address + other.size
+                && other.guest_address < region.guest_address + region.size
+            {
+                return Err(TdxIoctlError::TdxInvalidMemRegion(format!(
+                    "region at {:#x} with size {:#x} overlaps region at {:#x} with size {:#x}",
+                    region.guest_address, region.size, other.guest_address, other.size
+                )));
+            }
+        }
+    }
+
+    Ok(())
+}
+
 /// Initialize TDX vcpu
 pub fn tdx_init_vcpu(vcpu_fd: &RawFd, hob_address: u64) -> std::result::Result<(), TdxIoctlError> {
     tdx_command(vcpu_fd, TdxCommand::InitVcpu, 0, hob_address).map_err(TdxIoctlError::TdxInitVcpu)
@@ -218,3 +287,51 @@ pub fn tdx_get_caps(kvm_fd: &RawFd) -> std::result::Result<TdxCaps, TdxIoctlErro
     .map_err(TdxIoctlError::TdxCapabilities)?;
     Ok(tdx_caps)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MEM_END: u64 = 0x100000;
+
+    fn region(guest_address: u64, size: u64) -> TdxMemoryRegion {
+        TdxMemoryRegion {
+            guest_address,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_tdx_validate_memory_regions() {
+        // disjoint in-bounds regions are fine, in any order
+        let regions = [region(0x3000, 0x1000), region(0x0, 0x2000)];
+        tdx_validate_memory_regions(&regions, MEM_END).unwrap();
+
+        // empty region
+        let regions = [region(0x1000, 0)];
+        assert!(tdx_validate_memory_regions(&regions, MEM_END).is_err());
+
+        // unaligned region
+        let regions = [region(0x800, 0x1000)];
+        assert!(tdx_validate_memory_regions(&regions, MEM_END).is_err());
+
+        // region beyond the end of guest memory
+        let regions = [region(MEM_END - 0x1000, 0x2000)];
+        assert!(tdx_validate_memory_regions(&regions, MEM_END).is_err());
+
+        // region wrapping around the address space
+        let regions = [region(u64::MAX - 0xfff, 0x2000)];
+        assert!(tdx_validate_memory_regions(&regions, MEM_END).is_err());
+
+        // a wrapping region next to a valid one must yield an error, not an
+        // arithmetic overflow in the overlap check
+        let regions = [region(0x0, 0x1000), region(u64::MAX - 0xfff, 0x2000)];
+        let err = tdx_validate_memory_regions(&regions, MEM_END).unwrap_err();
+        assert!(matches!(err, TdxIoctlError::TdxInvalidMemRegion(_)));
+
+        // overlapping regions corrupt the measurement
+        let regions = [region(0x0, 0x3000), region(0x2000, 0x2000)];
+        let err = tdx_validate_memory_regions(&regions, MEM_END).unwrap_err();
+        assert!(matches!(err, TdxIoctlError::TdxInvalidMemRegion(_)));
+    }
+}
@@ -891,6 +891,9 @@ pub struct Mem<AS: GuestAddressSpace> {
     id: String,
     phantom: PhantomData<AS>,
     alignment: u64,
+    // boot memory size in bytes, already extended over the low MMIO hole by
+    // the caller, used as the lower boundary for hot-added regions
+    boot_mem_size: u64,
     // used for liveupgrade to record the memory state map in epoll handler
     mem_state_map: Option<Vec<bool>>,
     multi_region: bool,
@@ -989,6 +992,7 @@ impl<AS: GuestAddressSpace> Mem<AS> {
             id,
             phantom: PhantomData,
             alignment,
+            boot_mem_size: boot_mem_byte,
             mem_state_map: None,
             multi_region,
             map_regions: Arc::new(Mutex::new(Vec::new())),
@@ -1219,8 +1223,16 @@ where
         // kernel would cause some memory unusable which outside the alignment.
         // Then, the memory needs to be above 4G to avoid conflicts with
         // lapic/ioapic devices.
+        // Also place the region start past boot memory (which the caller has
+        // already extended over the low MMIO hole), so the first hot-added
+        // region can never overlap boot RAM or the hole.
+        let min_start = self
+            .boot_mem_size
+            .checked_add(self.alignment - 1)
+            .map(|v| v & !(self.alignment - 1))
+            .unwrap_or(self.boot_mem_size);
         requests.push(ResourceConstraint::MemAddress {
-            range: None,
+            range: Some((min_start, u64::MAX)),
             align: self.alignment,
             size: config.region_size,
         });
@@ -1778,10 +1790,12 @@ pub(crate) mod tests {
         );
         assert_eq!(requirements[2], ResourceConstraint::LegacyIrq { irq: None });
         assert_eq!(requirements[3], ResourceConstraint::GenericIrq { size: 2 });
+        // the hot-add region is constrained to start above boot memory,
+        // aligned up to the memory block alignment
         assert_eq!(
             requirements[4],
             ResourceConstraint::MemAddress {
-                range: None,
+                range: Some((0xc0000000, u64::MAX)),
                 align: VIRTIO_MEM_DEFAULT_BLOCK_ALIGNMENT,
                 size: 0x100 << 20,
             }
@@ -1795,6 +1809,43 @@ pub(crate) mod tests {
         );
     }
 
+    #[test]
+    fn test_mem_hot_add_region_starts_above_boot_mem() {
+        let epoll_mgr = EpollManager::default();
+        let id = "mem0".to_string();
+        let factory = Arc::new(Mutex::new(DummyMemRegionFactory {}));
+        // boot memory size not aligned to the memory block alignment
+        let boot_mem_byte = 0xc0000000u64 + 0x100000;
+        let dev = Mem::<Arc<GuestMemoryMmap>>::new(
+            id,
+            0x100,
+            0x100,
+            false,
+            None,
+            epoll_mgr,
+            factory,
+            boot_mem_byte,
+        )
+        .unwrap();
+        let mut requirements = vec![];
+        VirtioDevice::<Arc<GuestMemoryMmap<()>>, QueueSync, GuestRegionMmap>::get_resource_requirements(
+            &dev, &mut requirements, false,
+        );
+        match requirements[1] {
+            ResourceConstraint::MemAddress {
+                range: Some((min_start, _)),
+                align,
+                ..
+            } => {
+                // rounded up to the next block alignment boundary, above boot memory
+                assert_eq!(min_start, 0xc8000000);
+                assert!(min_start >= boot_mem_byte);
+                assert_eq!(min_start % align, 0);
+            }
+            _ => panic!("expected a lower-bounded memory address constraint"),
+        }
+    }
+
     #[test]
     fn test_mem_virtio_device_set_resource() {
         let epoll_mgr = EpollManager::default();
@@ -61,6 +61,10 @@ pub enum BalloonDeviceError {
     #[error("balloon device does not exist")]
     NotExist,
 
+    /// virtio features cannot be renegotiated on a live device
+    #[error("updating deflate-on-oom on balloon device '{0}' requires a device reset")]
+    RequiresReset(String),
+
     /// The device manager errors.
     #[error("DeviceManager error: {0}")]
     DeviceManager(#[source] DeviceMgrError),
@@ -250,11 +254,11 @@ impl BalloonDeviceMgr {
         index: usize,
         size_mib: u64,
     ) -> std::result::Result<(), BalloonDeviceError> {
-        let device = self.info_list[index]
-            .device
-            .as_ref()
-            .ok_or_else(|| BalloonDeviceError::NotExist)?;
-        if let Some(mmio_dev) = device.as_any().downcast_ref::<DbsMmioV2Device>() {
+        let info = &self.info_list[index];
+        if info.device().is_none() {
+            return Err(BalloonDeviceError::NotExist);
+        }
+        if let Some(mmio_dev) = info.downcast_device::<DbsMmioV2Device>() {
             let guard = mmio_dev.state();
             let inner_dev = guard.get_inner_device();
             if let Some(balloon_dev) = inner_dev
@@ -269,6 +273,27 @@ impl BalloonDeviceMgr {
         Ok(())
     }
 
+    /// Update the deflate-on-oom setting of the balloon device `balloon_id`.
+    ///
+    /// Virtio features are negotiated once when the guest driver probes the
+    /// device and virtio-balloon does not support live renegotiation, so the
+    /// new value can only be applied while the device has not been created
+    /// yet. Updating a live device returns a "requires reset" error.
+    pub fn update_deflate_on_oom(
+        &mut self,
+        balloon_id: &str,
+        enabled: bool,
+    ) -> std::result::Result<(), BalloonDeviceError> {
+        let index = self
+            .get_index_of_balloon_dev(balloon_id)
+            .ok_or_else(|| BalloonDeviceError::InvalidDeviceId(balloon_id.to_string()))?;
+        if self.info_list[index].device.is_some() {
+            return Err(BalloonDeviceError::RequiresReset(balloon_id.to_string()));
+        }
+        self.info_list[index].config.f_deflate_on_oom = enabled;
+        Ok(())
+    }
+
     fn get_index_of_balloon_dev(&self, balloon_id: &str) -> Option<usize> {
         self.info_list
             .iter()
@@ -297,7 +322,7 @@ impl Drop for BalloonDeviceMgr {
 mod tests {
     use super::*;
     use crate::device_manager::tests::create_address_space;
-    use crate::test_utils::tests::create_vm_for_test;
+    use crate::test_utils::tests::{create_vm_for_test, serde_roundtrip};
 
     impl Default for BalloonDeviceConfigInfo {
         fn default() -> Self {
@@ -312,6 +337,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_balloon_config_serde_roundtrip() {
+        let config = BalloonDeviceConfigInfo {
+            balloon_id: "balloon0".to_string(),
+            size_mib: 64,
+            f_deflate_on_oom: true,
+            ..Default::default()
+        };
+        assert_eq!(serde_roundtrip(&config), config);
+    }
+
     #[test]
     fn test_balloon_config_check_conflicts() {
         let config = BalloonDeviceConfigInfo::default();
@@ -435,4 +471,68 @@ mod tests {
             .update_balloon_size(0, 200)
             .is_ok());
     }
+
+    #[test]
+    fn test_balloon_update_deflate_on_oom() {
+        //Init vm for test.
+        let mut vm = create_vm_for_test();
+        let device_op_ctx = DeviceOpContext::new(
+            Some(vm.epoll_manager().clone()),
+            vm.device_manager(),
+            Some(vm.vm_as().unwrap().clone()),
+            Some(create_address_space()),
+            false,
+            Some(vm.vm_config().clone()),
+            vm.shared_info().clone(),
+        );
+
+        let dummy_balloon_device = BalloonDeviceConfigInfo::default();
+        vm.device_manager_mut()
+            .balloon_manager
+            .insert_or_update_device(device_op_ctx, dummy_balloon_device)
+            .unwrap();
+
+        // unknown device id
+        assert!(matches!(
+            vm.device_manager_mut()
+                .balloon_manager
+                .update_deflate_on_oom("no-such-balloon", true),
+            Err(BalloonDeviceError::InvalidDeviceId(_))
+        ));
+
+        // before the device is created the new value is applied to the config
+        assert!(vm
+            .device_manager_mut()
+            .balloon_manager
+            .update_deflate_on_oom("", true)
+            .is_ok());
+        assert!(
+            vm.device_manager().balloon_manager.info_list[0]
+                .config
+                .f_deflate_on_oom
+        );
+
+        let mut device_op_ctx = DeviceOpContext::new(
+            Some(vm.epoll_manager().clone()),
+            vm.device_manager(),
+            Some(vm.vm_as().unwrap().clone()),
+            Some(create_address_space()),
+            false,
+            Some(vm.vm_config().clone()),
+            vm.shared_info().clone(),
+        );
+        assert!(vm
+            .device_manager_mut()
+            .balloon_manager
+            .attach_devices(&mut device_op_ctx)
+            .is_ok());
+
+        // once the device is live the feature cannot be renegotiated
+        assert!(matches!(
+            vm.device_manager_mut()
+                .balloon_manager
+                .update_deflate_on_oom("", false),
+            Err(BalloonDeviceError::RequiresReset(_))
+        ));
+    }
 }
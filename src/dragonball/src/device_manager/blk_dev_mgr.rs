@@ -15,6 +15,7 @@ use std::os::unix::io::AsRawFd;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
+use dbs_utils::rate_limiter::RateLimiter;
 use dbs_virtio_devices as virtio;
 use dbs_virtio_devices::block::{aio::Aio, io_uring::IoUring, Block, LocalFile, Ufile};
 #[cfg(feature = "vhost-user-blk")]
@@ -595,15 +596,7 @@ impl BlockDeviceMgr {
             }
         };
 
-        let mut limiters = vec![];
-        for _i in 0..cfg.num_queues {
-            if let Some(limiter) = cfg.rate_limiter.clone().map(|mut v| {
-                v.resize(cfg.num_queues as u64);
-                v.try_into().unwrap()
-            }) {
-                limiters.push(limiter);
-            }
-        }
+        let limiters = Self::build_rate_limiters(cfg)?;
 
         Ok(Box::new(Block::new(
             block_files,
@@ -614,6 +607,22 @@ impl BlockDeviceMgr {
         )?))
     }
 
+    /// Build one rate limiter per queue from the device configuration, each
+    /// queue getting 1/num_queues of the configured budget.
+    fn build_rate_limiters(
+        cfg: &BlockDeviceConfigInfo,
+    ) -> std::result::Result<Vec<RateLimiter>, virtio::Error> {
+        let mut limiters = vec![];
+        if let Some(limiter_cfg) = cfg.rate_limiter.as_ref() {
+            for _i in 0..cfg.num_queues {
+                let mut per_queue_cfg = limiter_cfg.clone();
+                per_queue_cfg.resize(cfg.num_queues as u64);
+                limiters.push(per_queue_cfg.try_into()?);
+            }
+        }
+        Ok(limiters)
+    }
+
     #[cfg(feature = "vhost-user-blk")]
     fn create_vhost_user_device(
         cfg: &BlockDeviceConfigInfo,
@@ -846,8 +855,9 @@ mod tests {
     use vmm_sys_util::tempfile::TempFile;
 
     use super::*;
+    use crate::config_manager::TokenBucketConfigInfo;
     use crate::device_manager::tests::create_address_space;
-    use crate::test_utils::tests::create_vm_for_test;
+    use crate::test_utils::tests::{create_vm_for_test, serde_roundtrip};
 
     #[test]
     fn test_block_device_type() {
@@ -867,6 +877,53 @@ mod tests {
         assert_eq!(mgr.info_list.len(), 0);
     }
 
+    #[test]
+    fn test_block_device_config_serde_roundtrip() {
+        let config = BlockDeviceConfigInfo {
+            drive_id: "drive0".to_string(),
+            path_on_host: PathBuf::from("/dev/vda"),
+            rate_limiter: Some(RateLimiterConfigInfo::default()),
+            ..Default::default()
+        };
+        assert_eq!(serde_roundtrip(&config), config);
+    }
+
+    #[test]
+    fn test_build_rate_limiters() {
+        // without a limiter config no limiters are built
+        let mut config = BlockDeviceConfigInfo::default();
+        assert!(BlockDeviceMgr::build_rate_limiters(&config)
+            .unwrap()
+            .is_empty());
+
+        // the configured budget is split evenly across the queues
+        config.num_queues = 2;
+        config.rate_limiter = Some(RateLimiterConfigInfo {
+            bandwidth: TokenBucketConfigInfo {
+                size: 1000,
+                one_time_burst: 100,
+                refill_time: 10,
+            },
+            ops: TokenBucketConfigInfo {
+                size: 500,
+                one_time_burst: 50,
+                refill_time: 20,
+            },
+        });
+        let limiters = BlockDeviceMgr::build_rate_limiters(&config).unwrap();
+        assert_eq!(limiters.len(), 2);
+        for limiter in limiters.iter() {
+            let bandwidth = limiter.bandwidth().unwrap();
+            assert_eq!(bandwidth.capacity(), 500);
+            assert_eq!(bandwidth.one_time_burst(), 50);
+            assert_eq!(bandwidth.refill_time_ms(), 10);
+            let ops = limiter.ops().unwrap();
+            assert_eq!(ops.capacity(), 250);
+            assert_eq!(ops.one_time_burst(), 25);
+            assert_eq!(ops.refill_time_ms(), 20);
+        }
+    }
+
     #[test]
     fn test_add_non_root_block_device() {
         skip_if_not_root!();
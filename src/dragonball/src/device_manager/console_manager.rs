@@ -52,6 +52,30 @@ enum Backend {
     SockPath(String),
 }
 
+/// Configuration of the host side terminal attached to a console backend.
+///
+/// Terminal modes only apply to stdio backends, Unix domain socket backends
+/// never touch the host terminal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ConsoleConfig {
+    /// Put the host terminal into raw mode.
+    pub raw_mode: bool,
+    /// Echo input back to the host terminal. Only meaningful when raw mode is
+    /// disabled, a raw terminal never echoes. Disabling both gives a
+    /// read-only console for non-interactive logging.
+    pub echo: bool,
+}
+
+impl Default for ConsoleConfig {
+    /// The default preserves the historical behavior: raw mode without echo.
+    fn default() -> Self {
+        ConsoleConfig {
+            raw_mode: true,
+            echo: false,
+        }
+    }
+}
+
 /// Console manager to manage frontend and backend console devices.
 pub struct ConsoleManager {
     epoll_mgr: EpollManager,
@@ -74,6 +98,16 @@ impl ConsoleManager {
 
     /// Create a console backend device by using stdio streams.
     pub fn create_stdio_console(&mut self, device: Arc<Mutex<SerialDevice>>) -> Result<()> {
+        self.create_stdio_console_with_config(device, ConsoleConfig::default())
+    }
+
+    /// Create a console backend device by using stdio streams, with explicit
+    /// control over the host terminal mode.
+    pub fn create_stdio_console_with_config(
+        &mut self,
+        device: Arc<Mutex<SerialDevice>>,
+        config: ConsoleConfig,
+    ) -> Result<()> {
         device
             .lock()
             .unwrap()
@@ -81,10 +115,27 @@ impl ConsoleManager {
         let stdin_handle = std::io::stdin();
         {
             let guard = stdin_handle.lock();
-            guard
-                .set_raw_mode()
-                .map_err(ConsoleManagerError::StdinHandle)
-                .map_err(DeviceMgrError::ConsoleManager)?;
+            // Under process supervisors like systemd, stdin is usually a pipe
+            // or a file. Terminal attributes don't apply there, so fall back
+            // to line-buffered reads instead of failing console creation.
+            // Safe because isatty() only inspects the file descriptor.
+            if unsafe { libc::isatty(guard.tty_fd()) } == 1 {
+                if config.raw_mode {
+                    guard
+                        .set_raw_mode()
+                        .map_err(ConsoleManagerError::StdinHandle)
+                        .map_err(DeviceMgrError::ConsoleManager)?;
+                } else if !config.echo {
+                    Self::suppress_echo(guard.tty_fd())
+                        .map_err(ConsoleManagerError::StdinHandle)
+                        .map_err(DeviceMgrError::ConsoleManager)?;
+                }
+            } else {
+                slog::info!(
+                    self.logger,
+                    "stdin is not a terminal, skipping terminal mode setup"
+                );
+            }
             guard
                 .set_non_block(true)
                 .map_err(ConsoleManagerError::StdinHandle)
@@ -126,6 +177,23 @@ impl ConsoleManager {
         Ok(())
     }
 
+    // Clear the echo flags of the terminal while keeping it in canonical mode.
+    fn suppress_echo(
+        fd: std::os::unix::io::RawFd,
+    ) -> std::result::Result<(), vmm_sys_util::errno::Error> {
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::uninit();
+        // Safe because tcgetattr initializes the buffer on success.
+        if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } < 0 {
+            return Err(vmm_sys_util::errno::Error::last());
+        }
+        let mut termios = unsafe { termios.assume_init() };
+        termios.c_lflag &= !(libc::ECHO | libc::ECHONL);
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &termios) } < 0 {
+            return Err(vmm_sys_util::errno::Error::last());
+        }
+        Ok(())
+    }
+
     fn bind_domain_socket(serial_path: &str) -> std::result::Result<UnixListener, std::io::Error> {
         let path = Path::new(serial_path);
         if path.is_file() {
@@ -446,5 +514,32 @@ mod tests {
         writer.flush().unwrap();
     }
 
+    #[test]
+    fn test_create_stdio_console_without_tty() {
+        // stdin is a pipe under `cargo test`, console creation must still
+        // succeed by skipping the terminal mode setup
+        let logger = create_logger();
+        let mut mgr = ConsoleManager::new(EpollManager::default(), &logger);
+        let device = Arc::new(Mutex::new(SerialDevice::new(
+            vmm_sys_util::eventfd::EventFd::new(libc::EFD_NONBLOCK).unwrap(),
+        )));
+        mgr.create_stdio_console(device).unwrap();
+    }
+
+    #[test]
+    fn test_console_config_flags() {
+        // the default keeps the historical raw-mode behavior
+        let config = ConsoleConfig::default();
+        assert!(config.raw_mode);
+        assert!(!config.echo);
+
+        // a read-only logging console runs cooked with echo suppressed
+        let config = ConsoleConfig {
+            raw_mode: false,
+            echo: false,
+        };
+        assert_ne!(config, ConsoleConfig::default());
+    }
+
     // TODO: add unit tests for console manager
 }
@@ -79,6 +79,10 @@ pub enum MemDeviceError {
     #[error("invalid mem device id '{0}'")]
     InvalidDeviceId(String),
 
+    /// The guest NUMA node id doesn't exist in the VM's NUMA topology.
+    #[error("invalid guest NUMA node id {0}")]
+    InvalidGuestNumaNodeId(u16),
+
     /// The device manager errors.
     #[error("DeviceManager error: {0}")]
     DeviceManager(#[source] DeviceMgrError),
@@ -103,6 +107,10 @@ pub struct MemDeviceConfigInfo {
     pub use_shared_irq: Option<bool>,
     /// Use generic irq
     pub use_generic_irq: Option<bool>,
+    /// Treat failures of advisory memory tuning (madvise/mbind) as hard
+    /// errors instead of logging them and continuing
+    #[serde(default)]
+    pub strict_memory_advice: bool,
 }
 
 impl ConfigItem for MemDeviceConfigInfo {
@@ -277,6 +285,7 @@ impl MemDeviceMgr {
             ctx,
             config.mem_id.clone(),
             config.host_numa_node_id,
+            config.strict_memory_advice,
         )?));
 
         let mut capacity_mib = config.capacity_mib;
@@ -322,11 +331,11 @@ impl MemDeviceMgr {
         index: usize,
         size_mib: u64,
     ) -> std::result::Result<(), MemDeviceError> {
-        let device = self.info_list[index]
-            .device
-            .as_ref()
-            .ok_or_else(|| MemDeviceError::DeviceNotExist)?;
-        if let Some(mmio_dev) = device.as_any().downcast_ref::<DbsMmioV2Device>() {
+        let info = &self.info_list[index];
+        if info.device().is_none() {
+            return Err(MemDeviceError::DeviceNotExist);
+        }
+        if let Some(mmio_dev) = info.downcast_device::<DbsMmioV2Device>() {
             let guard = mmio_dev.state();
             let inner_dev = guard.get_inner_device();
             if let Some(mem_dev) = inner_dev
@@ -361,6 +370,10 @@ struct MemoryRegionFactory {
     logger: Arc<slog::Logger>,
     host_numa_node_id: Option<u32>,
     instance_id: String,
+    // Whether failures of advisory memory tuning (madvise/mbind) are treated
+    // as hard errors. These calls only affect performance, so they are
+    // best-effort by default and just logged on failure.
+    strict_memory_advice: bool,
 }
 
 impl MemoryRegionFactory {
@@ -368,6 +381,7 @@ impl MemoryRegionFactory {
         ctx: &DeviceOpContext,
         mem_id: String,
         host_numa_node_id: Option<u32>,
+        strict_memory_advice: bool,
     ) -> Result<Self, DeviceMgrError> {
         let vm_as = ctx.get_vm_as()?;
         let address_space = ctx.get_address_space()?;
@@ -386,6 +400,7 @@ impl MemoryRegionFactory {
             logger,
             host_numa_node_id,
             instance_id,
+            strict_memory_advice,
         })
     }
 
@@ -418,6 +433,9 @@ impl MemoryRegionFactory {
             )
         };
         if res < 0 {
+            if self.strict_memory_advice {
+                return Err(VirtioError::IOError(io::Error::last_os_error()));
+            }
             warn!(
                 self.logger,
                 "failed to mbind memory to host_numa_node_id {}: this may affect performance",
@@ -438,14 +456,23 @@ impl MemoryRegionFactory {
         );
 
         // Safe because we just create the MmapRegion
-        unsafe {
+        if let Err(e) = unsafe {
             mman::madvise(
                 mmap_reg.as_ptr() as *mut libc::c_void,
                 mmap_reg.size(),
                 mman::MmapAdvise::MADV_HUGEPAGE,
             )
+        } {
+            if self.strict_memory_advice {
+                return Err(VirtioError::Madvise(e));
+            }
+            warn!(
+                self.logger,
+                "failed to madvise(MADV_HUGEPAGE): {}: this may affect performance",
+                e;
+                "subsystem" => "virito-mem"
+            );
         }
-        .map_err(VirtioError::Madvise)?;
 
         Ok(())
     }
@@ -590,6 +617,27 @@ impl MemRegionFactory for MemoryRegionFactory {
         &self,
         guest_addr: GuestAddress,
     ) -> std::result::Result<*mut u8, VirtioError> {
+        // Reject guest addresses outside any known address space region, e.g.
+        // a stale address restored after live upgrade. Otherwise the address
+        // would be translated against an unrelated mapping and return a
+        // misleading host pointer.
+        let mut in_region = false;
+        let _ = self.address_space.walk_regions(|region| {
+            if guest_addr >= region.start_addr() && guest_addr <= region.last_addr() {
+                in_region = true;
+            }
+            Ok(())
+        });
+        if !in_region {
+            return Err(VirtioError::IOError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "guest address {:#x} is outside of any address space region",
+                    guest_addr.0
+                ),
+            )));
+        }
+
         let memory = self.vm_as.memory();
         // NOTE: We can't clone `GuestRegionMmap` reference directly!!!
         //
@@ -621,7 +669,7 @@ mod tests {
     use vm_memory::GuestMemoryRegion;
 
     use super::*;
-    use crate::test_utils::tests::create_vm_for_test;
+    use crate::test_utils::tests::{create_vm_for_test, serde_roundtrip};
 
     impl Default for MemDeviceConfigInfo {
         fn default() -> Self {
@@ -634,10 +682,23 @@ mod tests {
                 guest_numa_node_id: None,
                 use_generic_irq: None,
                 use_shared_irq: None,
+                strict_memory_advice: false,
             }
         }
     }
 
+    #[test]
+    fn test_mem_config_serde_roundtrip() {
+        let config = MemDeviceConfigInfo {
+            mem_id: "mem0".to_string(),
+            size_mib: 128,
+            host_numa_node_id: Some(1),
+            guest_numa_node_id: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(serde_roundtrip(&config), config);
+    }
+
     #[test]
     fn test_mem_config_check_conflicts() {
         let config = MemDeviceConfigInfo::default();
@@ -726,8 +787,78 @@ mod tests {
         let kvm_slot = 2;
 
         // no vfio manager, no numa node
-        let mut factory = MemoryRegionFactory::new(&ctx, mem_id, None).unwrap();
+        let mut factory = MemoryRegionFactory::new(&ctx, mem_id, None, false).unwrap();
         let region_opt = factory.create_region(guest_addr, region_len, kvm_slot);
         assert_eq!(region_opt.unwrap().len(), region_len);
     }
+
+    #[test]
+    fn test_mem_strict_memory_advice() {
+        let vm = create_vm_for_test();
+        let ctx = DeviceOpContext::new(
+            Some(vm.epoll_manager().clone()),
+            vm.device_manager(),
+            Some(vm.vm_as().unwrap().clone()),
+            vm.vm_address_space().cloned(),
+            true,
+            Some(VmConfigInfo::default()),
+            vm.shared_info().clone(),
+        );
+        let mem_id = String::from("mem0");
+        let mut factory = MemoryRegionFactory::new(&ctx, mem_id.clone(), None, false).unwrap();
+
+        // reserve a page-aligned range and unmap it, so memory advice on it
+        // deterministically fails
+        let size = 0x1000;
+        let addr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        assert_ne!(addr, libc::MAP_FAILED);
+        unsafe { libc::munmap(addr, size) };
+        let mmap_reg = unsafe { MmapRegion::build_raw(addr as *mut u8, size, 0, 0) }.unwrap();
+
+        // best-effort by default: failures are only logged
+        assert!(factory.configure_thp(&mmap_reg).is_ok());
+        assert!(factory.configure_numa(&mmap_reg, MAX_NODE - 1).is_ok());
+
+        // strict mode turns the same failures into hard errors
+        let mut strict_factory = MemoryRegionFactory::new(&ctx, mem_id, None, true).unwrap();
+        assert!(matches!(
+            strict_factory.configure_thp(&mmap_reg),
+            Err(VirtioError::Madvise(_))
+        ));
+        assert!(matches!(
+            strict_factory.configure_numa(&mmap_reg, MAX_NODE - 1),
+            Err(VirtioError::IOError(_))
+        ));
+    }
+
+    #[test]
+    fn test_mem_restore_region_addr_out_of_range() {
+        let vm = create_vm_for_test();
+        let ctx = DeviceOpContext::new(
+            Some(vm.epoll_manager().clone()),
+            vm.device_manager(),
+            Some(vm.vm_as().unwrap().clone()),
+            vm.vm_address_space().cloned(),
+            true,
+            Some(VmConfigInfo::default()),
+            vm.shared_info().clone(),
+        );
+        let mem_id = String::from("mem0");
+
+        let factory = MemoryRegionFactory::new(&ctx, mem_id, None, false).unwrap();
+        // an address far beyond any address space region is rejected instead
+        // of being translated into a misleading host pointer
+        let stale_addr = GuestAddress(u64::MAX - 0x1000);
+        let res = factory.restore_region_addr(stale_addr);
+        assert!(matches!(res, Err(VirtioError::IOError(_))));
+    }
 }
@@ -743,8 +743,11 @@ impl DeviceManager {
         _ctx: &mut DeviceOpContext,
     ) -> std::result::Result<(), StartMicroVmError> {
         // Connect serial ports to the console and dmesg_fifo.
-        self.set_guest_kernel_log_stream(dmesg_fifo)
-            .map_err(|_| StartMicroVmError::EventFd)?;
+        self.set_guest_kernel_log_stream(dmesg_fifo).map_err(|e| {
+            StartMicroVmError::DeviceManager(DeviceMgrError::LegacyManager(legacy::Error::EventFd(
+                e,
+            )))
+        })?;
 
         info!(self.logger, "init console path: {:?}", com1_sock_path);
         if let Some(legacy_manager) = self.legacy_manager.as_ref() {
@@ -1339,6 +1342,25 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_legacy_manager_error_format() {
+        let error = DeviceMgrError::LegacyManager(legacy::Error::EventFd(
+            io::Error::from_raw_os_error(libc::ENOENT),
+        ));
+        assert_eq!(
+            error.to_string(),
+            "failure while reading EventFd file descriptor"
+        );
+
+        let error = DeviceMgrError::LegacyManager(legacy::Error::IrqManager(
+            vmm_sys_util::errno::Error::new(libc::EINVAL),
+        ));
+        assert_eq!(
+            error.to_string(),
+            "failure while managing interrupt for legacy device"
+        );
+    }
+
     #[test]
     fn test_create_device_manager() {
         skip_if_not_root!();
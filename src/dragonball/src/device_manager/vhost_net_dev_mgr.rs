@@ -283,10 +283,25 @@ mod tests {
             vhost_net_dev_mgr::{VhostNetDeviceConfigInfo, VhostNetDeviceError, VhostNetDeviceMgr},
             DeviceManager, DeviceMgrError, DeviceOpContext,
         },
-        test_utils::tests::create_vm_for_test,
+        test_utils::tests::{create_vm_for_test, serde_roundtrip},
         vm::VmConfigInfo,
     };
 
+    #[test]
+    fn test_vhost_net_config_serde_roundtrip() {
+        let config = VhostNetDeviceConfigInfo {
+            iface_id: "eth0".to_string(),
+            host_dev_name: "tap0".to_string(),
+            num_queues: 2,
+            queue_size: 256,
+            guest_mac: Some(MacAddr::parse_str("01:23:45:67:89:0a").unwrap()),
+            allow_duplicate_mac: false,
+            use_shared_irq: None,
+            use_generic_irq: None,
+        };
+        assert_eq!(serde_roundtrip(&config), config);
+    }
+
     #[test]
     fn test_create_vhost_net_device() {
         let vm = create_vm_for_test();
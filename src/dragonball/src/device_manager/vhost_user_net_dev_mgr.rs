@@ -218,7 +218,19 @@ impl Default for VhostUserNetDeviceMgr {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::tests::create_vm_for_test;
+    use crate::test_utils::tests::{create_vm_for_test, serde_roundtrip};
+
+    #[test]
+    fn test_vhost_user_net_config_serde_roundtrip() {
+        let config = VhostUserNetDeviceConfigInfo {
+            sock_path: "/tmp/vhost.sock".to_string(),
+            num_queues: 2,
+            queue_size: 128,
+            guest_mac: Some(MacAddr::parse_str("01:23:45:67:89:0a").unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(serde_roundtrip(&config), config);
+    }
 
     #[test]
     fn test_create_vhost_user_net_device() {
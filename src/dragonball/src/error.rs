@@ -124,6 +124,10 @@ pub enum StartMicroVmError {
     #[error("failure while configuring the virtual machine: {0}")]
     ConfigureVm(#[source] vm::VmError),
 
+    /// Cannot enable CPU power management features on this host.
+    #[error("failure while enabling CPU power management features: {0}")]
+    CpuPm(String),
+
     /// Cannot load initrd.
     #[error("cannot load Initrd into guest memory: {0}")]
     InitrdLoader(#[from] LoadInitrdError),
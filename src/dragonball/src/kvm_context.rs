@@ -87,10 +87,35 @@ impl KvmContext {
 mod x86_64 {
     use super::*;
     use dbs_arch::msr::*;
-    use kvm_bindings::{kvm_msr_entry, CpuId, MsrList, Msrs};
+    use kvm_bindings::{kvm_msr_entry, CpuId, MsrList, Msrs, KVM_CAP_X86_DISABLE_EXITS};
     use std::collections::HashSet;
+    use std::os::raw::c_ulong;
+    use vmm_sys_util::ioctl::ioctl_with_val;
+    use vmm_sys_util::{ioctl_io_nr, ioctl_ioc_nr};
+
+    ioctl_io_nr!(KVM_CHECK_EXTENSION, 0xAE, 0x03);
 
     impl KvmContext {
+        /// Get the bitmask of vm exits the host allows to be disabled, see
+        /// `KVM_CAP_X86_DISABLE_EXITS` and the `KVM_X86_DISABLE_EXITS_*`
+        /// flags. Returns 0 when the host does not support the capability.
+        pub fn supported_disable_exits(&self) -> u32 {
+            // Safe because we know `kvm` holds a valid KVM fd and the kernel
+            // only returns an integer for KVM_CHECK_EXTENSION.
+            let ret = unsafe {
+                ioctl_with_val(
+                    &self.kvm,
+                    KVM_CHECK_EXTENSION(),
+                    KVM_CAP_X86_DISABLE_EXITS as c_ulong,
+                )
+            };
+            if ret < 0 {
+                0
+            } else {
+                ret as u32
+            }
+        }
+
         /// Get information about supported CPUID of x86 processor.
         pub fn supported_cpuid(
             &self,
@@ -68,3 +68,52 @@ pub const EXIT_CODE_INVALID_JSON: u8 = 151;
 pub const EXIT_CODE_BAD_CONFIGURATION: u8 = 152;
 /// Command line arguments parsing error.
 pub const EXIT_CODE_ARG_PARSING: u8 = 153;
+
+/// Map a process exit code back to a human-readable reason for logging and
+/// telemetry. Unknown codes fall back to `"unknown exit code"`.
+pub fn exit_code_reason(code: u8) -> &'static str {
+    match code {
+        EXIT_CODE_OK => "success",
+        EXIT_CODE_GENERIC_ERROR => "generic error",
+        EXIT_CODE_UNEXPECTED_ERROR => "unexpected error",
+        EXIT_CODE_BAD_SYSCALL => "intercepted a restricted system call",
+        EXIT_CODE_SIGBUS => "intercepted SIGBUS",
+        EXIT_CODE_SIGSEGV => "intercepted SIGSEGV",
+        EXIT_CODE_INVALID_JSON => "invalid json configuration",
+        EXIT_CODE_BAD_CONFIGURATION => "bad resource configuration",
+        EXIT_CODE_ARG_PARSING => "command line arguments parsing error",
+        _ => "unknown exit code",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_code_reason_distinct() {
+        let codes = [
+            EXIT_CODE_OK,
+            EXIT_CODE_GENERIC_ERROR,
+            EXIT_CODE_UNEXPECTED_ERROR,
+            EXIT_CODE_BAD_SYSCALL,
+            EXIT_CODE_SIGBUS,
+            EXIT_CODE_SIGSEGV,
+            EXIT_CODE_INVALID_JSON,
+            EXIT_CODE_BAD_CONFIGURATION,
+            EXIT_CODE_ARG_PARSING,
+        ];
+
+        // each defined exit code maps to its own reason
+        let reasons: Vec<&str> = codes.iter().map(|c| exit_code_reason(*c)).collect();
+        for (i, reason) in reasons.iter().enumerate() {
+            assert_ne!(*reason, "unknown exit code");
+            for other in reasons.iter().skip(i + 1) {
+                assert_ne!(reason, other);
+            }
+        }
+
+        // undefined codes hit the fallback
+        assert_eq!(exit_code_reason(42), "unknown exit code");
+    }
+}
@@ -49,6 +49,10 @@ pub enum ResourceError {
     /// No resource available.
     #[error("no resource available")]
     NoAvailResource,
+
+    /// Two resource bundles claim the same exclusive resource.
+    #[error("conflicting resource assignment: {0}")]
+    ResourceConflict(String),
 }
 
 #[derive(Default)]
@@ -65,28 +69,46 @@ struct ResourceManagerBuilder {
     mem_pool: IntervalTree<()>,
     // IntervalTree for allocating kvm memory slot.
     kvm_mem_slot_pool: IntervalTree<()>,
+    // Effective MSI irq range, None until the msi_irq_pool is initialized.
+    msi_irq_range: Option<(u32, u32)>,
 }
 
 impl ResourceManagerBuilder {
     /// init legacy_irq_pool with arch specific constants.
-    fn init_legacy_irq_pool(mut self) -> Self {
+    pub(crate) fn init_legacy_irq_pool_helper(pool: &mut IntervalTree<()>) {
         // The LEGACY_IRQ_BASE irq is reserved for shared IRQ and won't be allocated / reallocated,
         // so we don't insert it into the legacy_irq interval tree.
-        self.legacy_irq_pool
-            .insert(Range::new(LEGACY_IRQ_BASE + 1, LEGACY_IRQ_MAX), None);
+        pool.insert(Range::new(LEGACY_IRQ_BASE + 1, LEGACY_IRQ_MAX), None);
+    }
+
+    /// init legacy_irq_pool with helper function
+    fn init_legacy_irq_pool(mut self) -> Self {
+        Self::init_legacy_irq_pool_helper(&mut self.legacy_irq_pool);
         self
     }
 
-    /// init msi_irq_pool with arch specific constants.
-    fn init_msi_irq_pool(mut self) -> Self {
-        self.msi_irq_pool
-            .insert(Range::new(MSI_IRQ_BASE, MSI_IRQ_MAX), None);
+    /// init msi_irq_pool with arch specific constants, or a custom range for
+    /// platforms whose GSI layout differs from the defaults.
+    pub(crate) fn init_msi_irq_pool_helper(pool: &mut IntervalTree<()>, range: Option<(u32, u32)>) {
+        let (base, max) = range.unwrap_or((MSI_IRQ_BASE, MSI_IRQ_MAX));
+        pool.insert(Range::new(base, max), None);
+    }
+
+    /// init msi_irq_pool with helper function
+    fn init_msi_irq_pool(mut self, range: Option<(u32, u32)>) -> Self {
+        self.msi_irq_range = Some(range.unwrap_or((MSI_IRQ_BASE, MSI_IRQ_MAX)));
+        Self::init_msi_irq_pool_helper(&mut self.msi_irq_pool, range);
         self
     }
 
     /// init pio_pool with arch specific constants.
+    pub(crate) fn init_pio_pool_helper(pool: &mut IntervalTree<()>) {
+        pool.insert(Range::new(PIO_MIN, PIO_MAX), None);
+    }
+
+    /// init pio_pool with helper function
     fn init_pio_pool(mut self) -> Self {
-        self.pio_pool.insert(Range::new(PIO_MIN, PIO_MAX), None);
+        Self::init_pio_pool_helper(&mut self.pio_pool);
         self
     }
 
@@ -114,6 +136,21 @@ impl ResourceManagerBuilder {
             }
         }
 
+        #[cfg(target_arch = "aarch64")]
+        {
+            // The GIC distributor/redistributor registers occupy the space
+            // below MAPPED_IO_START. Today the pool starts right at the GIC
+            // end, but mark the GIC range as occupied whenever the window
+            // covers it so device allocations can never collide with the
+            // interrupt controller.
+            if MMIO_LOW_START < dbs_arch::gic::GIC_REG_END_ADDRESS {
+                mmio.insert(
+                    Range::new(MMIO_LOW_START, dbs_arch::gic::GIC_REG_END_ADDRESS - 1),
+                    Some(()),
+                );
+            }
+        }
+
         if *GUEST_MEM_END < *GUEST_PHYS_END {
             mmio.insert(Range::new(*GUEST_MEM_END + 1, *GUEST_PHYS_END), None);
         }
@@ -152,10 +189,17 @@ impl ResourceManagerBuilder {
     }
 
     /// init kvm_mem_slot_pool with arch specific constants.
-    fn init_kvm_mem_slot_pool(mut self, max_kvm_mem_slot: Option<usize>) -> Self {
+    pub(crate) fn init_kvm_mem_slot_pool_helper(
+        pool: &mut IntervalTree<()>,
+        max_kvm_mem_slot: Option<usize>,
+    ) {
         let max_slots = max_kvm_mem_slot.unwrap_or(KVM_USER_MEM_SLOTS as usize);
-        self.kvm_mem_slot_pool
-            .insert(Range::new(0, max_slots as u64), None);
+        pool.insert(Range::new(0, max_slots as u64), None);
+    }
+
+    /// init kvm_mem_slot_pool with helper function
+    fn init_kvm_mem_slot_pool(mut self, max_kvm_mem_slot: Option<usize>) -> Self {
+        Self::init_kvm_mem_slot_pool_helper(&mut self.kvm_mem_slot_pool, max_kvm_mem_slot);
         self
     }
 
@@ -167,6 +211,7 @@ impl ResourceManagerBuilder {
             mmio_pool: Mutex::new(self.mmio_pool),
             mem_pool: Mutex::new(self.mem_pool),
             kvm_mem_slot_pool: Mutex::new(self.kvm_mem_slot_pool),
+            msi_irq_range: self.msi_irq_range.unwrap_or((MSI_IRQ_BASE, MSI_IRQ_MAX)),
         }
     }
 }
@@ -179,6 +224,7 @@ pub struct ResourceManager {
     mmio_pool: Mutex<IntervalTree<()>>,
     mem_pool: Mutex<IntervalTree<()>>,
     kvm_mem_slot_pool: Mutex<IntervalTree<()>>,
+    msi_irq_range: (u32, u32),
 }
 
 impl Default for ResourceManager {
@@ -190,10 +236,19 @@ impl Default for ResourceManager {
 impl ResourceManager {
     /// Create a resource manager instance.
     pub fn new(max_kvm_mem_slot: Option<usize>) -> Self {
+        Self::new_with_msi_irq_range(max_kvm_mem_slot, None)
+    }
+
+    /// Create a resource manager instance with a custom MSI irq range, for
+    /// platforms (or tests) whose GSI layout differs from the arch defaults.
+    pub fn new_with_msi_irq_range(
+        max_kvm_mem_slot: Option<usize>,
+        msi_irq_range: Option<(u32, u32)>,
+    ) -> Self {
         let res_manager_builder = ResourceManagerBuilder::default();
         res_manager_builder
             .init_legacy_irq_pool()
-            .init_msi_irq_pool()
+            .init_msi_irq_pool(msi_irq_range)
             .init_pio_pool()
             .init_mmio_pool()
             .init_mem_pool()
@@ -202,9 +257,18 @@ impl ResourceManager {
     }
 
     /// Init mem_pool with arch specific constants.
-    pub fn init_mem_pool(&self) {
+    ///
+    /// The pool is only populated when it is still empty, so calling this
+    /// again, e.g. after restoring from a migration snapshot, does not
+    /// duplicate the arch ranges. Returns whether the pool got populated.
+    pub fn init_mem_pool(&self) -> bool {
         let mut mem = self.mem_pool.lock().unwrap();
+        if !mem.is_empty() {
+            return false;
+        }
         ResourceManagerBuilder::init_mem_pool_helper(&mut mem);
+
+        true
     }
 
     /// Check if mem_pool is empty.
@@ -212,6 +276,61 @@ impl ResourceManager {
         self.mem_pool.lock().unwrap().is_empty()
     }
 
+    /// Init legacy_irq_pool with arch specific constants.
+    pub fn init_legacy_irq_pool(&self) {
+        let mut pool = self.legacy_irq_pool.lock().unwrap();
+        ResourceManagerBuilder::init_legacy_irq_pool_helper(&mut pool);
+    }
+
+    /// Check if legacy_irq_pool is empty.
+    pub fn is_legacy_irq_pool_empty(&self) -> bool {
+        self.legacy_irq_pool.lock().unwrap().is_empty()
+    }
+
+    /// Init msi_irq_pool with the range the manager was constructed with.
+    pub fn init_msi_irq_pool(&self) {
+        let mut pool = self.msi_irq_pool.lock().unwrap();
+        ResourceManagerBuilder::init_msi_irq_pool_helper(&mut pool, Some(self.msi_irq_range));
+    }
+
+    /// Check if msi_irq_pool is empty.
+    pub fn is_msi_irq_pool_empty(&self) -> bool {
+        self.msi_irq_pool.lock().unwrap().is_empty()
+    }
+
+    /// Init pio_pool with arch specific constants.
+    pub fn init_pio_pool(&self) {
+        let mut pool = self.pio_pool.lock().unwrap();
+        ResourceManagerBuilder::init_pio_pool_helper(&mut pool);
+    }
+
+    /// Check if pio_pool is empty.
+    pub fn is_pio_pool_empty(&self) -> bool {
+        self.pio_pool.lock().unwrap().is_empty()
+    }
+
+    /// Init mmio_pool with arch specific constants.
+    pub fn init_mmio_pool(&self) {
+        let mut pool = self.mmio_pool.lock().unwrap();
+        ResourceManagerBuilder::init_mmio_pool_helper(&mut pool);
+    }
+
+    /// Check if mmio_pool is empty.
+    pub fn is_mmio_pool_empty(&self) -> bool {
+        self.mmio_pool.lock().unwrap().is_empty()
+    }
+
+    /// Init kvm_mem_slot_pool with arch specific constants.
+    pub fn init_kvm_mem_slot_pool(&self, max_kvm_mem_slot: Option<usize>) {
+        let mut pool = self.kvm_mem_slot_pool.lock().unwrap();
+        ResourceManagerBuilder::init_kvm_mem_slot_pool_helper(&mut pool, max_kvm_mem_slot);
+    }
+
+    /// Check if kvm_mem_slot_pool is empty.
+    pub fn is_kvm_mem_slot_pool_empty(&self) -> bool {
+        self.kvm_mem_slot_pool.lock().unwrap().is_empty()
+    }
+
     /// Allocate one legacy irq number.
     ///
     /// Allocate the specified irq number if `fixed` contains an irq number.
@@ -292,10 +411,11 @@ impl ResourceManager {
     ///
     /// Panic if `irq` or `count` is invalid.
     pub fn free_msi_irq(&self, irq: u32, count: u32) -> Result<(), ResourceError> {
-        if irq < MSI_IRQ_BASE
+        let (msi_irq_base, msi_irq_max) = self.msi_irq_range;
+        if irq < msi_irq_base
             || count == 0
             || irq.checked_add(count).is_none()
-            || irq + count - 1 > MSI_IRQ_MAX
+            || irq + count - 1 > msi_irq_max
         {
             return Err(ResourceError::InvalidResourceRange("MSI IRQ".to_string()));
         }
@@ -343,6 +463,22 @@ impl ResourceManager {
         self.allocate_mmio_address(&constraint)
     }
 
+    /// Allocate a MMIO address range aligned to `align` within the window
+    /// `[base, end]` and returns the allocated base address.
+    pub fn allocate_mmio_address_in_range(
+        &self,
+        base: u64,
+        end: u64,
+        size: u64,
+        align: u64,
+    ) -> Option<u64> {
+        if base > end || size == 0 || end - base + 1 < size {
+            return None;
+        }
+        let constraint = Constraint::new(size).align(align).min(base).max(end);
+        self.allocate_mmio_address(&constraint)
+    }
+
     /// Allocate a MMIO address range and returns the allocated base address.
     pub fn allocate_mmio_address(&self, constraint: &Constraint) -> Option<u64> {
         // Safe to unwrap() because we don't expect poisoned lock here.
@@ -571,6 +707,61 @@ impl ResourceManager {
         }
         Ok(())
     }
+
+    /// Merge two allocated resource bundles, e.g. a base device allocation
+    /// and a hotplug extension, into a single bundle for bookkeeping.
+    /// Address ranges are simply concatenated, while exclusive assignments
+    /// such as interrupts and KVM memory slots must not appear in both
+    /// bundles.
+    pub fn merge_device_resources(
+        base: &DeviceResources,
+        extension: &DeviceResources,
+    ) -> Result<DeviceResources, ResourceError> {
+        for res in extension.iter() {
+            match res {
+                Resource::LegacyIrq(irq) if base.get_legacy_irq() == Some(*irq) => {
+                    return Err(ResourceError::ResourceConflict(format!(
+                        "legacy irq {} is assigned in both bundles",
+                        irq
+                    )));
+                }
+                Resource::MsiIrq { ty, base: start, size } => {
+                    for other in base.iter() {
+                        if let Resource::MsiIrq {
+                            ty: other_ty,
+                            base: other_start,
+                            size: other_size,
+                        } = other
+                        {
+                            if ty == other_ty
+                                && *start < other_start + other_size
+                                && *other_start < start + size
+                            {
+                                return Err(ResourceError::ResourceConflict(format!(
+                                    "msi irq range {}+{} overlaps assigned range {}+{}",
+                                    start, size, other_start, other_size
+                                )));
+                            }
+                        }
+                    }
+                }
+                Resource::KvmMemSlot(slot) if base.get_kvm_mem_slots().contains(slot) => {
+                    return Err(ResourceError::ResourceConflict(format!(
+                        "kvm memory slot {} is assigned in both bundles",
+                        slot
+                    )));
+                }
+                _ => {}
+            }
+        }
+
+        let mut merged = base.clone();
+        for res in extension.iter() {
+            merged.append(res.clone());
+        }
+
+        Ok(merged)
+    }
 }
 
 #[cfg(test)]
@@ -741,6 +932,19 @@ mod tests {
                 .max(0xffff_ffffu64);
             assert!(mgr.allocate_mmio_address(&constraint).is_none());
         }
+        #[cfg(target_arch = "aarch64")]
+        {
+            // Can't allocate from the GIC register space
+            let constraint = Constraint::new(0x1000u64)
+                .min(0)
+                .max(dbs_arch::gic::GIC_REG_END_ADDRESS - 1);
+            assert!(mgr.allocate_mmio_address(&constraint).is_none());
+
+            // Allocations above the GIC succeed and never dip below its end
+            let constraint = Constraint::new(0x1000u64);
+            let base = mgr.allocate_mmio_address(&constraint).unwrap();
+            assert!(base >= dbs_arch::gic::GIC_REG_END_ADDRESS);
+        }
         let constraint = Constraint::new(0x100_0000u64).min(0x1_0000_0000u64 - 0x200_0000u64);
         assert!(mgr.allocate_mmio_address(&constraint).is_some());
 
@@ -778,4 +982,142 @@ mod tests {
         assert!(mgr.allocate_mem_address(&constraint_1).is_some());
         assert!(mgr.allocate_mem_address(&constraint_2).is_some());
     }
+
+    #[test]
+    fn test_custom_msi_irq_range() {
+        // 32 MSI irqs starting at 256 instead of the arch defaults
+        let mgr = ResourceManager::new_with_msi_irq_range(None, Some((256, 287)));
+
+        assert_eq!(mgr.allocate_msi_irq(16).unwrap(), 256);
+        assert_eq!(mgr.allocate_msi_irq(16).unwrap(), 272);
+        // the pool is exhausted now
+        assert!(mgr.allocate_msi_irq(1).is_none());
+
+        // freeing validates against the custom range, not the defaults
+        mgr.free_msi_irq(256, 16).unwrap();
+        assert_eq!(mgr.allocate_msi_irq(16).unwrap(), 256);
+        assert_eq!(
+            mgr.free_msi_irq(255, 1),
+            Err(ResourceError::InvalidResourceRange("MSI IRQ".to_string()))
+        );
+        assert_eq!(
+            mgr.free_msi_irq(280, 16),
+            Err(ResourceError::InvalidResourceRange("MSI IRQ".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_pool_empty_detection() {
+        // a default-built manager has all pools uninitialized, mirroring the
+        // state a live-upgrade resume starts from
+        let mgr = ResourceManager::default();
+        assert!(mgr.is_legacy_irq_pool_empty());
+        assert!(mgr.is_msi_irq_pool_empty());
+        assert!(mgr.is_pio_pool_empty());
+        assert!(mgr.is_mmio_pool_empty());
+        assert!(mgr.is_mem_pool_empty());
+        assert!(mgr.is_kvm_mem_slot_pool_empty());
+
+        // after re-initialization every pool must be populated again
+        mgr.init_legacy_irq_pool();
+        mgr.init_msi_irq_pool();
+        mgr.init_pio_pool();
+        mgr.init_mmio_pool();
+        assert!(mgr.init_mem_pool());
+        mgr.init_kvm_mem_slot_pool(None);
+        assert!(!mgr.is_legacy_irq_pool_empty());
+        assert!(!mgr.is_msi_irq_pool_empty());
+        assert!(!mgr.is_pio_pool_empty());
+        assert!(!mgr.is_mmio_pool_empty());
+        assert!(!mgr.is_mem_pool_empty());
+        assert!(!mgr.is_kvm_mem_slot_pool_empty());
+
+        // a fully constructed manager starts out populated
+        let mgr = ResourceManager::new(None);
+        assert!(!mgr.is_legacy_irq_pool_empty());
+        assert!(!mgr.is_kvm_mem_slot_pool_empty());
+    }
+
+    #[test]
+    fn test_init_mem_pool_twice() {
+        let mgr = ResourceManager::default();
+        assert!(mgr.is_mem_pool_empty());
+        assert!(mgr.init_mem_pool());
+        assert!(!mgr.is_mem_pool_empty());
+
+        // the pool is already populated, the second call must be a no-op
+        // instead of duplicating the arch ranges
+        assert!(!mgr.init_mem_pool());
+        assert!(!mgr.is_mem_pool_empty());
+    }
+
+    #[test]
+    fn test_allocate_mmio_address_in_range() {
+        let mgr = ResourceManager::new(None);
+
+        // an exact-fit window yields its base address
+        let base = mgr
+            .allocate_mmio_address_in_range(MMIO_LOW_START, MMIO_LOW_START + 0xfff, 0x1000, 0x1000)
+            .unwrap();
+        assert_eq!(base, MMIO_LOW_START);
+
+        // the window is occupied now
+        assert!(mgr
+            .allocate_mmio_address_in_range(MMIO_LOW_START, MMIO_LOW_START + 0xfff, 0x1000, 0x1000)
+            .is_none());
+
+        // a window smaller than the requested size can never fit
+        assert!(mgr
+            .allocate_mmio_address_in_range(
+                MMIO_LOW_START + 0x1000,
+                MMIO_LOW_START + 0x1fff,
+                0x2000,
+                0x1000
+            )
+            .is_none());
+
+        // inverted windows are rejected
+        assert!(mgr
+            .allocate_mmio_address_in_range(MMIO_LOW_START + 0x2000, MMIO_LOW_START, 0x1000, 1)
+            .is_none());
+    }
+
+    #[test]
+    fn test_merge_device_resources() {
+        let mut base = DeviceResources::new();
+        base.append(Resource::MmioAddressRange {
+            base: 0x1000,
+            size: 0x1000,
+        });
+        base.append(Resource::LegacyIrq(5));
+        base.append(Resource::KvmMemSlot(3));
+
+        let mut extension = DeviceResources::new();
+        extension.append(Resource::MmioAddressRange {
+            base: 0x4000,
+            size: 0x1000,
+        });
+        extension.append(Resource::KvmMemSlot(4));
+
+        let merged = ResourceManager::merge_device_resources(&base, &extension).unwrap();
+        assert_eq!(merged.iter().count(), 5);
+        assert_eq!(merged.get_mmio_address_ranges().len(), 2);
+        assert_eq!(merged.get_kvm_mem_slots(), vec![3, 4]);
+
+        // a bundle claiming an already assigned memory slot is rejected
+        let mut conflicting = DeviceResources::new();
+        conflicting.append(Resource::KvmMemSlot(3));
+        assert!(matches!(
+            ResourceManager::merge_device_resources(&base, &conflicting),
+            Err(ResourceError::ResourceConflict(_))
+        ));
+
+        // so is one claiming the already assigned legacy irq
+        let mut conflicting = DeviceResources::new();
+        conflicting.append(Resource::LegacyIrq(5));
+        assert!(matches!(
+            ResourceManager::merge_device_resources(&base, &conflicting),
+            Err(ResourceError::ResourceConflict(_))
+        ));
+    }
 }
@@ -11,6 +11,15 @@ pub mod tests {
     use std::sync::{Arc, RwLock};
     use vmm_sys_util::tempfile::TempFile;
 
+    /// Round-trip a value through its JSON representation, for checking that
+    /// device configuration types survive snapshotting.
+    pub fn serde_roundtrip<T>(value: &T) -> T
+    where
+        T: serde::Serialize + serde::de::DeserializeOwned,
+    {
+        serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap()
+    }
+
     pub fn create_vm_for_test() -> Vm {
         // Call for kvm too frequently would cause error in some host kernel.
         let instance_info = Arc::new(RwLock::new(InstanceInfo::default()));
@@ -8,7 +8,7 @@ mod vcpu_impl;
 mod vcpu_manager;
 
 use dbs_arch::VpmuFeatureLevel;
-pub use vcpu_manager::{VcpuManager, VcpuManagerError, VcpuResizeInfo};
+pub use vcpu_manager::{VcpuManager, VcpuManagerError, VcpuResizeInfo, VcpuRunState};
 
 #[cfg(feature = "hotplug")]
 pub use vcpu_manager::VcpuResizeError;
@@ -310,6 +310,10 @@ pub struct Vcpu {
     // CPUID information for the x86_64 CPU
     #[cfg(target_arch = "x86_64")]
     cpuid: kvm_bindings::CpuId,
+    // MSRs supported by the host for this vCPU, needed to save/restore the
+    // guest MSR state, e.g. for live migration.
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) supported_msrs: kvm_bindings::MsrList,
 
     /// Multiprocessor affinity register recorded for aarch64
     #[cfg(target_arch = "aarch64")]
@@ -846,6 +850,9 @@ pub mod tests {
         let supported_cpuid = kvm_context
             .supported_cpuid(kvm_bindings::KVM_MAX_CPUID_ENTRIES)
             .unwrap();
+        let supported_msrs = kvm_context
+            .supported_msrs(kvm_bindings::KVM_MAX_MSR_ENTRIES)
+            .unwrap();
         let reset_event_fd = EventFd::new(libc::EFD_NONBLOCK).unwrap();
         let vcpu_state_event = EventFd::new(libc::EFD_NONBLOCK).unwrap();
         let (tx, rx) = channel();
@@ -856,6 +863,7 @@ pub mod tests {
             vcpu_fd,
             io_manager,
             supported_cpuid,
+            supported_msrs,
             reset_event_fd,
             vcpu_state_event,
             tx,
@@ -899,6 +907,15 @@ pub mod tests {
         (vcpu, rx)
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_vcpu_supported_msrs() {
+        skip_if_not_root!();
+
+        let (vcpu, _) = create_vcpu();
+        assert!(vcpu.supported_msrs().as_fam_struct_ref().nmsrs > 0);
+    }
+
     #[test]
     fn test_vcpu_run_emulation() {
         skip_if_not_root!();
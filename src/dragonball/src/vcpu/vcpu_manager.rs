@@ -37,7 +37,7 @@ use crate::vcpu::VcpuConfig;
 use crate::vm::VmConfigInfo;
 use crate::IoManagerCached;
 
-/// the timeout for communication with vcpu threads
+/// the default timeout for communication with vcpu threads
 const CPU_RECV_TIMEOUT_MS: u64 = 1000;
 
 /// vCPU manager error
@@ -95,6 +95,10 @@ pub enum VcpuManagerError {
     #[error("Event fd is already set for the vcpu")]
     EventAlreadyExist,
 
+    /// Reset event fd has not been set before creating vcpus.
+    #[error("reset event fd is not set for the vcpu manager")]
+    ResetEventFdNotSet,
+
     /// Response channel error
     #[error("Response channel error: {0}")]
     VcpuResponseChannel(RecvError),
@@ -178,6 +182,16 @@ enum VcpuAction {
     Hotunplug,
 }
 
+/// Run state of a present vcpu as tracked by the vcpu manager.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum VcpuRunState {
+    /// the vcpu thread is running
+    #[default]
+    Running,
+    /// the vcpu thread is paused
+    Paused,
+}
+
 /// VcpuResizeInfo describes the information for vcpu hotplug / hot-unplug
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
 pub struct VcpuResizeInfo {
@@ -192,6 +206,7 @@ pub(crate) struct VcpuInfo {
     vcpu_fd: Option<Arc<VcpuFd>>,
     handle: Option<VcpuHandle>,
     tid: u32,
+    run_state: VcpuRunState,
 }
 
 impl std::fmt::Debug for VcpuInfo {
@@ -201,6 +216,7 @@ impl std::fmt::Debug for VcpuInfo {
             .field("vcpu_fd", &self.vcpu_fd.is_some())
             .field("handle", &self.handle.is_some())
             .field("tid", &self.tid)
+            .field("run_state", &self.run_state)
             .finish()
     }
 }
@@ -227,14 +243,25 @@ pub struct VcpuManager {
 
     action_sycn_tx: Option<Sender<bool>>,
     vcpus_in_action: (VcpuAction, Vec<u8>),
+    // Timeout for waiting on responses from vcpu threads, in milliseconds.
+    // On heavily loaded hosts the default may be too tight and can be raised
+    // with `set_recv_timeout_ms()`.
+    recv_timeout_ms: u64,
     pub(crate) reset_event_fd: Option<EventFd>,
 
+    // Number of IoManager cache revalidations, recorded so tests can check
+    // that device hot-add paths trigger a revalidation.
+    #[cfg(test)]
+    pub(crate) revalidate_cache_count: u64,
+
     #[cfg(all(feature = "hotplug", feature = "dbs-upcall"))]
     upcall_channel: Option<Arc<UpcallClient<DevMgrService>>>,
 
     // X86 specific fields.
     #[cfg(target_arch = "x86_64")]
     pub(crate) supported_cpuid: kvm_bindings::CpuId,
+    #[cfg(target_arch = "x86_64")]
+    pub(crate) supported_msrs: kvm_bindings::MsrList,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -283,6 +310,10 @@ impl VcpuManager {
         let supported_cpuid = kvm_context
             .supported_cpuid(kvm_bindings::KVM_MAX_CPUID_ENTRIES)
             .map_err(VcpuManagerError::Kvm)?;
+        #[cfg(target_arch = "x86_64")]
+        let supported_msrs = kvm_context
+            .supported_msrs(kvm_bindings::KVM_MAX_MSR_ENTRIES)
+            .map_err(VcpuManagerError::Kvm)?;
         #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
         let vpmu_feature_level = match vm_config_info.vpmu_feature {
             #[cfg(target_arch = "x86_64")]
@@ -320,11 +351,16 @@ impl VcpuManager {
             vm_fd,
             action_sycn_tx: None,
             vcpus_in_action: (VcpuAction::None, Vec::new()),
+            recv_timeout_ms: CPU_RECV_TIMEOUT_MS,
             reset_event_fd: None,
+            #[cfg(test)]
+            revalidate_cache_count: 0,
             #[cfg(all(feature = "hotplug", feature = "dbs-upcall"))]
             upcall_channel: None,
             #[cfg(target_arch = "x86_64")]
             supported_cpuid,
+            #[cfg(target_arch = "x86_64")]
+            supported_msrs,
         }));
 
         let handler = Box::new(VcpuEpollHandler {
@@ -359,6 +395,17 @@ impl VcpuManager {
         vcpus
     }
 
+    /// Override the timeout for waiting on responses from vcpu threads.
+    /// Defaults to 1000ms, which may be too tight on heavily loaded hosts.
+    pub fn set_recv_timeout_ms(&mut self, timeout_ms: u64) {
+        self.recv_timeout_ms = timeout_ms;
+    }
+
+    /// Timeout for waiting on responses from vcpu threads, in milliseconds.
+    pub fn recv_timeout_ms(&self) -> u64 {
+        self.recv_timeout_ms
+    }
+
     /// add reset event fd for each vcpu, if the reset_event_fd is already set, error will be returned.
     pub fn set_reset_event_fd(&mut self, reset_event_fd: EventFd) -> Result<()> {
         if self.reset_event_fd.is_some() {
@@ -468,9 +515,24 @@ impl VcpuManager {
 
     /// revalidate IoManager cache of all vcpus
     pub fn revalidate_all_vcpus_cache(&mut self) -> Result<()> {
+        #[cfg(test)]
+        {
+            self.revalidate_cache_count += 1;
+        }
         self.revalidate_vcpus_cache(&self.present_vcpus())
     }
 
+    /// Query the run state of a vcpu, `None` if the vcpu is not present.
+    ///
+    /// The state reflects the last pause/resume request issued by the vcpu
+    /// manager, so orchestrators can verify that a throttle took effect.
+    pub fn vcpu_state(&self, cpu_id: u8) -> Option<VcpuRunState> {
+        self.vcpu_infos
+            .get(cpu_id as usize)
+            .filter(|info| info.handle.is_some())
+            .map(|info| info.run_state)
+    }
+
     /// return all present vcpus
     pub fn present_vcpus(&self) -> Vec<u8> {
         self.vcpu_infos
@@ -490,8 +552,8 @@ impl VcpuManager {
         let present_vcpus_count = self.present_vcpus_count();
         let mut available_vcpus = Vec::new();
 
-        if present_vcpus_count < vcpu_count {
-            let mut size = vcpu_count - present_vcpus_count;
+        if present_vcpus_count < vcpu_count as usize {
+            let mut size = vcpu_count as usize - present_vcpus_count;
             for cpu_id in 0..self.vcpu_config.max_vcpu_count {
                 let info = &self.vcpu_infos[cpu_id as usize];
                 if info.handle.is_none() {
@@ -507,11 +569,12 @@ impl VcpuManager {
         available_vcpus
     }
 
-    /// Present vcpus count
-    fn present_vcpus_count(&self) -> u8 {
+    /// Present vcpus count. Counted as `usize` so the fold cannot wrap when
+    /// the number of present vcpus reaches the `u8` boundary.
+    fn present_vcpus_count(&self) -> usize {
         self.vcpu_infos
             .iter()
-            .fold(0, |sum, info| sum + info.handle.is_some() as u8)
+            .fold(0, |sum, info| sum + info.handle.is_some() as usize)
     }
 
     /// Configure single vcpu
@@ -602,7 +665,7 @@ impl VcpuManager {
             if let Some(handle) = &self.vcpu_infos[*cpu_id as usize].handle {
                 match handle
                     .response_receiver()
-                    .recv_timeout(Duration::from_millis(CPU_RECV_TIMEOUT_MS))
+                    .recv_timeout(Duration::from_millis(self.recv_timeout_ms))
                 {
                     Ok(VcpuResponse::Tid(_, id)) => self.vcpu_infos[*cpu_id as usize].tid = id,
                     Err(e) => {
@@ -652,7 +715,22 @@ impl VcpuManager {
         Ok(())
     }
 
-    fn pause_vcpus(&mut self, cpu_indexes: &[u8]) -> Result<()> {
+    // Ensure every id refers to a present vcpu before any event is sent, so
+    // that a bad id does not leave part of the subset already signalled.
+    fn validate_present_vcpus(&self, cpu_indexes: &[u8]) -> Result<()> {
+        for cpu_id in cpu_indexes {
+            match self.vcpu_infos.get(*cpu_id as usize) {
+                Some(info) if info.handle.is_some() => {}
+                _ => return Err(VcpuManagerError::VcpuNotFound(*cpu_id)),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// pause a subset of the present vcpus
+    pub fn pause_vcpus(&mut self, cpu_indexes: &[u8]) -> Result<()> {
+        self.validate_present_vcpus(cpu_indexes)?;
         for cpu_id in cpu_indexes {
             if self.vcpu_infos.get(*cpu_id as usize).is_none() {
                 return Err(VcpuManagerError::VcpuNotFound(*cpu_id));
@@ -666,10 +744,16 @@ impl VcpuManager {
             }
         }
 
+        for cpu_id in cpu_indexes {
+            self.vcpu_infos[*cpu_id as usize].run_state = VcpuRunState::Paused;
+        }
+
         Ok(())
     }
 
-    fn resume_vcpus(&mut self, cpu_indexes: &[u8]) -> Result<()> {
+    /// resume a subset of the present vcpus
+    pub fn resume_vcpus(&mut self, cpu_indexes: &[u8]) -> Result<()> {
+        self.validate_present_vcpus(cpu_indexes)?;
         for cpu_id in cpu_indexes {
             if self.vcpu_infos.get(*cpu_id as usize).is_none() {
                 return Err(VcpuManagerError::VcpuNotFound(*cpu_id));
@@ -683,6 +767,10 @@ impl VcpuManager {
             }
         }
 
+        for cpu_id in cpu_indexes {
+            self.vcpu_infos[*cpu_id as usize].run_state = VcpuRunState::Running;
+        }
+
         Ok(())
     }
 
@@ -734,7 +822,7 @@ impl VcpuManager {
         let present_vcpus_count = self.present_vcpus_count();
         if vcpu_count > self.vcpu_config.max_vcpu_count {
             return Err(VcpuManagerError::ExpectedVcpuExceedMax);
-        } else if vcpu_count < present_vcpus_count {
+        } else if (vcpu_count as usize) < present_vcpus_count {
             return Ok(Vec::new());
         }
 
@@ -778,6 +866,13 @@ impl VcpuManager {
         vcpu_fd: Arc<VcpuFd>,
         request_ts: TimestampUs,
     ) -> Result<Vcpu> {
+        let reset_event_fd = self
+            .reset_event_fd
+            .as_ref()
+            .ok_or(VcpuManagerError::ResetEventFdNotSet)?
+            .try_clone()
+            .map_err(VcpuManagerError::VcpuIO)?;
+
         // It's safe to unwrap because guest_kernel always exist until vcpu manager done
         Vcpu::new_x86_64(
             cpu_index,
@@ -785,7 +880,8 @@ impl VcpuManager {
             // safe to unwrap
             self.io_manager.as_ref().unwrap().clone(),
             self.supported_cpuid.clone(),
-            self.reset_event_fd.as_ref().unwrap().try_clone().unwrap(),
+            self.supported_msrs.clone(),
+            reset_event_fd,
             self.vcpu_state_event.try_clone().unwrap(),
             self.vcpu_state_sender.clone(),
             request_ts,
@@ -807,12 +903,19 @@ impl VcpuManager {
         vcpu_fd: Arc<VcpuFd>,
         request_ts: TimestampUs,
     ) -> Result<Vcpu> {
+        let reset_event_fd = self
+            .reset_event_fd
+            .as_ref()
+            .ok_or(VcpuManagerError::ResetEventFdNotSet)?
+            .try_clone()
+            .map_err(VcpuManagerError::VcpuIO)?;
+
         Vcpu::new_aarch64(
             cpu_index,
             vcpu_fd,
             // safe to unwrap
             self.io_manager.as_ref().unwrap().clone(),
-            self.reset_event_fd.as_ref().unwrap().try_clone().unwrap(),
+            reset_event_fd,
             self.vcpu_state_event.try_clone().unwrap(),
             self.vcpu_state_sender.clone(),
             request_ts,
@@ -838,8 +941,6 @@ mod hotplug {
 
     #[cfg(all(target_arch = "x86_64", feature = "dbs-upcall"))]
     use dbs_boot::mptable::APIC_VERSION;
-    #[cfg(target_arch = "aarch64")]
-    const APIC_VERSION: u8 = 0;
 
     #[cfg(feature = "dbs-upcall")]
     impl VcpuManager {
@@ -865,7 +966,7 @@ mod hotplug {
             if let Some(upcall) = self.upcall_channel.clone() {
                 let now_vcpu = self.present_vcpus_count();
                 info!("resize vcpu: now: {}, desire: {}", now_vcpu, vcpu_count);
-                match vcpu_count.cmp(&now_vcpu) {
+                match (vcpu_count as usize).cmp(&now_vcpu) {
                     Ordering::Equal => {
                         info!("resize vcpu: no need to resize");
                         self.sync_action_finish(false);
@@ -936,7 +1037,7 @@ mod hotplug {
             }
 
             let mut cpu_ids = self.calculate_removable_vcpus();
-            let cpu_num_to_be_del = (self.present_vcpus_count() - vcpu_count) as usize;
+            let cpu_num_to_be_del = self.present_vcpus_count() - vcpu_count as usize;
             if cpu_num_to_be_del >= cpu_ids.len() {
                 return Err(VcpuResizeError::LackRemovableVcpus(
                     cpu_ids.len() as u16,
@@ -1217,6 +1318,88 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_vcpu_manager_create_vcpus_without_reset_event_fd() {
+        skip_if_not_root!();
+        let instance_info = Arc::new(RwLock::new(InstanceInfo::default()));
+        let epoll_manager = EpollManager::default();
+        let mut vm = Vm::new(None, instance_info, epoll_manager).unwrap();
+        let vm_config = VmConfigInfo {
+            vcpu_count: 1,
+            max_vcpu_count: 1,
+            cpu_pm: "off".to_string(),
+            mem_type: "shmem".to_string(),
+            mem_file_path: "".to_string(),
+            mem_size_mib: 1,
+            serial_path: None,
+            cpu_topology: CpuTopology {
+                threads_per_core: 1,
+                cores_per_die: 1,
+                dies_per_socket: 1,
+                sockets: 1,
+            },
+            vpmu_feature: 0,
+            pci_hotplug_enabled: false,
+        };
+        vm.set_vm_config(vm_config);
+        vm.init_guest_memory().unwrap();
+
+        vm.init_vcpu_manager(vm.vm_as().unwrap().clone(), BpfProgram::default())
+            .unwrap();
+
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            vm.setup_interrupt_controller().unwrap();
+        }
+
+        // without a reset event fd, vcpu creation must fail cleanly rather
+        // than panicking inside create_vcpu_arch
+        let mut vcpu_manager = vm.vcpu_manager().unwrap();
+        let res = vcpu_manager.create_boot_vcpus(TimestampUs::default(), GuestAddress(0));
+        assert!(matches!(res, Err(VcpuManagerError::ResetEventFdNotSet)));
+    }
+
+    #[test]
+    fn test_vcpu_manager_recv_timeout() {
+        skip_if_not_root!();
+        *(EMULATE_RES.lock().unwrap()) = EmulationCase::Error(libc::EINTR);
+
+        let vm = get_vm();
+        let mut vcpu_manager = vm.vcpu_manager().unwrap();
+
+        // the default timeout is kept until it is overridden
+        assert_eq!(vcpu_manager.recv_timeout_ms(), CPU_RECV_TIMEOUT_MS);
+        vcpu_manager.set_recv_timeout_ms(2000);
+        assert_eq!(vcpu_manager.recv_timeout_ms(), 2000);
+
+        assert!(vcpu_manager
+            .create_boot_vcpus(TimestampUs::default(), GuestAddress(0))
+            .is_ok());
+        assert!(vcpu_manager.start_boot_vcpus(BpfProgram::default()).is_ok());
+
+        // a zero timeout expires before the vcpu thread can answer
+        vcpu_manager.set_recv_timeout_ms(0);
+        let res = vcpu_manager.get_vcpus_tid(&[0]);
+        assert!(matches!(res, Err(VcpuManagerError::VcpuGettid)));
+
+        // restoring a sane timeout makes the same request succeed
+        vcpu_manager.set_recv_timeout_ms(CPU_RECV_TIMEOUT_MS);
+        assert!(vcpu_manager.get_vcpus_tid(&[0]).is_ok());
+    }
+
+    #[test]
+    fn test_vcpu_manager_vcpu_count_boundary() {
+        skip_if_not_root!();
+        let vm = get_vm();
+        let mut vcpu_manager = vm.vcpu_manager().unwrap();
+
+        // a request at the u8 boundary is rejected instead of wrapping
+        let res = vcpu_manager.create_vcpus(u8::MAX, None, None);
+        assert!(matches!(res, Err(VcpuManagerError::ExpectedVcpuExceedMax)));
+        let res = vcpu_manager.activate_vcpus(u8::MAX, false);
+        assert!(matches!(res, Err(VcpuManagerError::ExpectedVcpuExceedMax)));
+    }
+
     #[test]
     fn test_vcpu_manager_boot_vcpus() {
         skip_if_not_root!();
@@ -1314,6 +1497,20 @@ mod tests {
         // pause and resume all
         assert!(vcpu_manager.pause_all_vcpus().is_ok());
         assert!(vcpu_manager.resume_all_vcpus().is_ok());
+
+        // start a second vcpu and pause only the first one: the second
+        // vcpu must stay responsive to state queries
+        assert!(vcpu_manager
+            .start_vcpus(2, BpfProgram::default(), false)
+            .is_ok());
+        assert!(vcpu_manager.pause_vcpus(&[0]).is_ok());
+        assert_eq!(vcpu_manager.vcpu_state(0), Some(VcpuRunState::Paused));
+        assert_eq!(vcpu_manager.vcpu_state(1), Some(VcpuRunState::Running));
+        assert!(vcpu_manager.get_vcpus_tid(&[1]).is_ok());
+        assert!(vcpu_manager.resume_vcpus(&[0]).is_ok());
+        assert_eq!(vcpu_manager.vcpu_state(0), Some(VcpuRunState::Running));
+        // a vcpu that was never started has no run state
+        assert_eq!(vcpu_manager.vcpu_state(2), None);
     }
 
     #[test]
@@ -1487,4 +1684,44 @@ mod tests {
         let res = vcpu_manager.resize_vcpu(0, None);
         assert!(matches!(res, Err(VcpuResizeError::Vcpu0CanNotBeRemoved)));
     }
+
+    // On aarch64 all vcpus are created at boot, but hotplug and hot-unplug
+    // still go through the upcall channel to notify the guest.
+    #[cfg(target_arch = "aarch64")]
+    #[test]
+    fn test_vcpu_manager_resize_cpu_add_del() {
+        skip_if_not_root!();
+        let vm = get_vm();
+        let mut vcpu_manager = vm.vcpu_manager().unwrap();
+
+        assert!(vcpu_manager
+            .create_boot_vcpus(TimestampUs::default(), GuestAddress(0))
+            .is_ok());
+        assert!(vcpu_manager.start_boot_vcpus(BpfProgram::default()).is_ok());
+
+        // init upcall channel
+        let dev_mgr_service = DevMgrService {};
+        let vsock_backend = VsockInnerBackend::new().unwrap();
+        let connector = vsock_backend.get_connector();
+        let epoll_manager = EpollManager::default();
+        let mut upcall_client =
+            UpcallClient::new(connector, epoll_manager, dev_mgr_service).unwrap();
+        assert!(upcall_client.connect().is_ok());
+        vcpu_manager.set_upcall_channel(Some(Arc::new(upcall_client)));
+
+        // add a vcpu
+        vcpu_manager.resize_vcpu(2, None).unwrap();
+        assert!(matches!(
+            vcpu_manager.get_vcpus_action(),
+            VcpuAction::Hotplug
+        ));
+
+        // hot-unplug it again
+        vcpu_manager.set_vcpus_action(VcpuAction::None, vec![]);
+        vcpu_manager.resize_vcpu(1, None).unwrap();
+        assert!(matches!(
+            vcpu_manager.get_vcpus_action(),
+            VcpuAction::Hotunplug
+        ));
+    }
 }
@@ -13,7 +13,7 @@ use dbs_arch::cpuid::{process_cpuid, VmSpec};
 use dbs_arch::gdt::gdt_entry;
 use dbs_utils::metric::IncMetric;
 use dbs_utils::time::TimestampUs;
-use kvm_bindings::CpuId;
+use kvm_bindings::{CpuId, MsrList};
 use kvm_ioctls::{VcpuFd, VmFd};
 use log::error;
 use vm_memory::{Address, GuestAddress, GuestAddressSpace};
@@ -34,6 +34,7 @@ impl Vcpu {
     /// * `vcpu_fd` - The kvm `VcpuFd` for the vcpu.
     /// * `io_mgr` - The io-manager used to access port-io and mmio devices.
     /// * `cpuid` - The `CpuId` listing the supported capabilities of this vcpu.
+    /// * `supported_msrs` - The `MsrList` of MSRs supported by the host for this vcpu.
     /// * `exit_evt` - An `EventFd` that will be written into when this vcpu
     ///   exits.
     /// * `vcpu_state_event` - The eventfd which can notify vmm state of some
@@ -48,6 +49,7 @@ impl Vcpu {
         vcpu_fd: Arc<VcpuFd>,
         io_mgr: IoManagerCached,
         cpuid: CpuId,
+        supported_msrs: MsrList,
         exit_evt: EventFd,
         vcpu_state_event: EventFd,
         vcpu_state_sender: Sender<VcpuStateEvent>,
@@ -72,9 +74,15 @@ impl Vcpu {
             support_immediate_exit,
             metrics: Arc::new(VcpuMetrics::default()),
             cpuid,
+            supported_msrs,
         })
     }
 
+    /// Get the list of MSRs supported by the host for this vcpu.
+    pub fn supported_msrs(&self) -> &MsrList {
+        &self.supported_msrs
+    }
+
     /// Configures a x86_64 specific vcpu and should be called once per vcpu.
     ///
     /// # Arguments
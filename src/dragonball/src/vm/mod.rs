@@ -292,6 +292,11 @@ impl Vm {
         self.address_space.get_address_space()
     }
 
+    /// Gets a reference to the address space manager owned by this VM.
+    pub fn address_space_mgr(&self) -> &AddressSpaceMgr {
+        &self.address_space
+    }
+
     /// Gets a reference to the address space for guest memory owned by this VM.
     ///
     /// Note that `GuestMemory` does not include any device memory that may have been added after
@@ -915,6 +920,65 @@ pub mod tests {
         assert!(vm.reset_console().is_ok());
     }
 
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_setup_reset_event_fd_absent() {
+        skip_if_not_root!();
+        let mut vm = create_vm_instance();
+        // no devices have been created yet, so there is no reset eventfd;
+        // this must surface as a structured error instead of a panic
+        let err = vm.setup_reset_event_fd().unwrap_err();
+        assert!(matches!(err, StartMicroVmError::DeviceManager(_)));
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_register_events_twice() {
+        use std::os::unix::io::AsRawFd;
+
+        use crate::vmm::tests::create_vmm_instance;
+
+        skip_if_not_root!();
+        let epoll_mgr = EpollManager::default();
+        let vmm = Arc::new(Mutex::new(create_vmm_instance(epoll_mgr.clone())));
+        let mut event_mgr = EventManager::new(&vmm, epoll_mgr).unwrap();
+
+        let mut vm = create_vm_instance();
+        // pretend the exit eventfd has been registered already
+        let reset_evt = EventFd::new(libc::EFD_NONBLOCK).unwrap();
+        let raw_fd = reset_evt.as_raw_fd();
+        vm.reset_eventfd = Some(reset_evt);
+
+        // a second registration is skipped instead of registering a
+        // duplicate exit fd
+        vm.register_events(&mut event_mgr).unwrap();
+        assert_eq!(vm.reset_eventfd.as_ref().unwrap().as_raw_fd(), raw_fd);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_setup_cpu_pm() {
+        use kvm_bindings::{KVM_X86_DISABLE_EXITS_HLT, KVM_X86_DISABLE_EXITS_MWAIT};
+
+        skip_if_not_root!();
+        let mut vm = create_vm_instance();
+
+        // cpu_pm off never touches the capability
+        vm.vm_config.cpu_pm = "off".to_string();
+        vm.setup_cpu_pm().unwrap();
+
+        vm.vm_config.cpu_pm = "on".to_string();
+        let wanted = KVM_X86_DISABLE_EXITS_HLT | KVM_X86_DISABLE_EXITS_MWAIT;
+        if vm.kvm.supported_disable_exits() & wanted == wanted {
+            // the host supports disabling idle exits, enabling must succeed
+            vm.setup_cpu_pm().unwrap();
+        } else {
+            // an unsupported host is reported as a clear error
+            let err = vm.setup_cpu_pm().unwrap_err();
+            assert!(matches!(err, StartMicroVmError::CpuPm(_)));
+        }
+    }
+
     #[test]
     fn test_vm_init_guest_memory() {
         skip_if_not_root!();
@@ -14,10 +14,13 @@ use dbs_address_space::AddressSpace;
 use dbs_boot::{add_e820_entry, bootparam, layout, mptable, BootParamsWrapper, InitrdConfig};
 use dbs_utils::epoll_manager::EpollManager;
 use dbs_utils::time::TimestampUs;
-use kvm_bindings::{kvm_irqchip, kvm_pit_config, kvm_pit_state2, KVM_PIT_SPEAKER_DUMMY};
+use kvm_bindings::{
+    kvm_enable_cap, kvm_irqchip, kvm_pit_config, kvm_pit_state2, KVM_CAP_X86_DISABLE_EXITS,
+    KVM_PIT_SPEAKER_DUMMY, KVM_X86_DISABLE_EXITS_HLT, KVM_X86_DISABLE_EXITS_MWAIT,
+};
 use linux_loader::cmdline::Cmdline;
 use linux_loader::configurator::{linux::LinuxBootConfigurator, BootConfigurator, BootParams};
-use slog::info;
+use slog::{info, warn};
 use vm_memory::{Address, GuestAddress, GuestAddressSpace, GuestMemory};
 
 use crate::address_space_manager::{GuestAddressSpaceImpl, GuestMemoryImpl};
@@ -180,16 +183,9 @@ impl Vm {
         self.create_pit()?;
         self.init_devices(epoll_mgr)?;
 
-        let reset_event_fd = self.device_manager.get_reset_eventfd().unwrap();
-        self.vcpu_manager()
-            .map_err(StartMicroVmError::Vcpu)?
-            .set_reset_event_fd(reset_event_fd)
-            .map_err(StartMicroVmError::Vcpu)?;
+        self.setup_reset_event_fd()?;
 
-        if self.vm_config.cpu_pm == "on" {
-            // TODO: add cpu_pm support. issue #4590.
-            info!(self.logger, "VM: enable CPU disable_idle_exits capability");
-        }
+        self.setup_cpu_pm()?;
 
         let vm_memory = vm_as.memory();
         let kernel_loader_result = self.load_kernel(vm_memory.deref())?;
@@ -260,7 +256,11 @@ impl Vm {
             .map_err(|e| StartMicroVmError::ConfigureVm(VmError::VmSetup(e)))
     }
 
-    /// Creates the irq chip and an in-kernel device model for the PIT.
+    /// Creates the irq chip in KVM.
+    ///
+    /// Interrupts are always routed through the in-kernel irqchip (PIC,
+    /// IOAPIC and LAPIC emulated by KVM); there is no userspace or split
+    /// irqchip mode.
     pub(crate) fn setup_interrupt_controller(
         &mut self,
     ) -> std::result::Result<(), StartMicroVmError> {
@@ -286,10 +286,67 @@ impl Vm {
             .map_err(|e| StartMicroVmError::ConfigureVm(VmError::VmSetup(e)))
     }
 
+    /// Configure CPU power management features for the guest.
+    ///
+    /// With `cpu_pm` set to "on", HLT and MWAIT instructions no longer
+    /// trigger vm exits (`KVM_CAP_X86_DISABLE_EXITS`), letting the guest
+    /// enter idle states without host intervention. Must be called before
+    /// any vCPU has been created.
+    pub(crate) fn setup_cpu_pm(&self) -> std::result::Result<(), StartMicroVmError> {
+        if self.vm_config.cpu_pm != "on" {
+            return Ok(());
+        }
+
+        let wanted = KVM_X86_DISABLE_EXITS_HLT | KVM_X86_DISABLE_EXITS_MWAIT;
+        let supported = self.kvm.supported_disable_exits();
+        if supported & wanted != wanted {
+            return Err(StartMicroVmError::CpuPm(format!(
+                "host kvm only allows disabling exits {:#x}, want {:#x}",
+                supported, wanted
+            )));
+        }
+
+        info!(self.logger, "VM: enable CPU disable_idle_exits capability");
+        let mut cap = kvm_enable_cap {
+            cap: KVM_CAP_X86_DISABLE_EXITS,
+            ..Default::default()
+        };
+        cap.args[0] = wanted as u64;
+        self.vm_fd
+            .enable_cap(&cap)
+            .map_err(|e| StartMicroVmError::CpuPm(format!("cannot disable idle exits: {}", e)))
+    }
+
+    /// Wire the device manager's reset eventfd up to the vcpu manager.
+    ///
+    /// The eventfd is created together with the legacy devices, so this
+    /// fails with a [`StartMicroVmError::DeviceManager`] error when the
+    /// legacy device manager has not been set up.
+    pub(crate) fn setup_reset_event_fd(&mut self) -> std::result::Result<(), StartMicroVmError> {
+        let reset_event_fd = self
+            .device_manager
+            .get_reset_eventfd()
+            .map_err(StartMicroVmError::DeviceManager)?;
+        self.vcpu_manager()
+            .map_err(StartMicroVmError::Vcpu)?
+            .set_reset_event_fd(reset_event_fd)
+            .map_err(StartMicroVmError::Vcpu)
+    }
+
     pub(crate) fn register_events(
         &mut self,
         event_mgr: &mut EventManager,
     ) -> std::result::Result<(), StartMicroVmError> {
+        if self.reset_eventfd.is_some() {
+            // a restart path may call this again; registering the exit
+            // eventfd twice would deliver duplicate exit events
+            warn!(
+                self.logger,
+                "VM: exit eventfd already registered, skipping"
+            );
+            return Ok(());
+        }
+
         let reset_evt = self
             .device_manager
             .get_reset_eventfd()
@@ -9,6 +9,9 @@
 //! This module depends on kubelet internal implementation details, a better way is needed
 //! to detect K8S EmptyDir medium type from `oci::spec::Mount` objects.
 
+use std::collections::HashMap;
+
+use kata_types::annotations::{cri_containerd, crio, dockershim};
 use kata_types::mount;
 use oci::Spec;
 
@@ -16,6 +19,44 @@ use crate::mount::get_linux_mount_info;
 
 pub use kata_types::k8s::is_empty_dir;
 
+/// Get the pod sandbox id from the well-known CRI annotation keys.
+///
+/// A pod sandbox container carries no sandbox id annotation, only regular containers running
+/// within a sandbox do, so `None` is returned for sandbox containers and for specs not created
+/// by a CRI implementation.
+pub fn sandbox_id_from_annotations(annotations: &HashMap<String, String>) -> Option<String> {
+    for key in [
+        cri_containerd::SANDBOX_ID_LABEL_KEY,
+        crio::SANDBOX_ID_LABEL_KEY,
+    ] {
+        if let Some(id) = annotations.get(key) {
+            if !id.is_empty() {
+                return Some(id.clone());
+            }
+        }
+    }
+
+    None
+}
+
+/// Check whether the annotations describe a pod sandbox container.
+///
+/// The container type annotation keys and values differ between CRI implementations, so all
+/// well-known variants are checked.
+pub fn is_sandbox_container(annotations: &HashMap<String, String>) -> bool {
+    for (key, sandbox) in [
+        (cri_containerd::CONTAINER_TYPE_LABEL_KEY, cri_containerd::SANDBOX),
+        (crio::CONTAINER_TYPE_LABEL_KEY, crio::SANDBOX),
+        (dockershim::CONTAINER_TYPE_LABEL_KEY, dockershim::SANDBOX),
+    ] {
+        if let Some(container_type) = annotations.get(key) {
+            return container_type == sandbox;
+        }
+    }
+
+    false
+}
+
 /// Check whether the given path is a kubernetes ephemeral volume.
 ///
 /// This method depends on a specific path used by k8s to detect if it's type of ephemeral.
@@ -69,3 +110,64 @@ pub fn update_ephemeral_storage_type(oci_spec: &mut Spec) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sandbox_id_from_annotations() {
+        let mut annotations = HashMap::new();
+        assert_eq!(sandbox_id_from_annotations(&annotations), None);
+
+        annotations.insert(
+            cri_containerd::SANDBOX_ID_LABEL_KEY.to_string(),
+            "".to_string(),
+        );
+        assert_eq!(sandbox_id_from_annotations(&annotations), None);
+
+        annotations.insert(
+            cri_containerd::SANDBOX_ID_LABEL_KEY.to_string(),
+            "5584".to_string(),
+        );
+        assert_eq!(
+            sandbox_id_from_annotations(&annotations),
+            Some("5584".to_string())
+        );
+
+        let mut annotations = HashMap::new();
+        annotations.insert(
+            crio::SANDBOX_ID_LABEL_KEY.to_string(),
+            "sandbox1".to_string(),
+        );
+        assert_eq!(
+            sandbox_id_from_annotations(&annotations),
+            Some("sandbox1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_is_sandbox_container() {
+        let mut annotations = HashMap::new();
+        assert!(!is_sandbox_container(&annotations));
+
+        annotations.insert(
+            cri_containerd::CONTAINER_TYPE_LABEL_KEY.to_string(),
+            cri_containerd::SANDBOX.to_string(),
+        );
+        assert!(is_sandbox_container(&annotations));
+
+        annotations.insert(
+            cri_containerd::CONTAINER_TYPE_LABEL_KEY.to_string(),
+            cri_containerd::CONTAINER.to_string(),
+        );
+        assert!(!is_sandbox_container(&annotations));
+
+        let mut annotations = HashMap::new();
+        annotations.insert(
+            dockershim::CONTAINER_TYPE_LABEL_KEY.to_string(),
+            dockershim::SANDBOX.to_string(),
+        );
+        assert!(is_sandbox_container(&annotations));
+    }
+}
@@ -217,6 +217,50 @@ pub fn create_mount_destination<S: AsRef<Path>, D: AsRef<Path>, R: AsRef<Path>>(
     }
 }
 
+/// Propagation type to apply to an existing mountpoint.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PropagationFlag {
+    /// Mount and unmount events propagate into and out of the mountpoint (MS_SHARED).
+    Shared,
+    /// Mount and unmount events only propagate into the mountpoint (MS_SLAVE).
+    Slave,
+    /// Mount and unmount events do not propagate in either direction (MS_PRIVATE).
+    Private,
+}
+
+impl From<PropagationFlag> for MsFlags {
+    fn from(flag: PropagationFlag) -> Self {
+        match flag {
+            PropagationFlag::Shared => MsFlags::MS_SHARED,
+            PropagationFlag::Slave => MsFlags::MS_SLAVE,
+            PropagationFlag::Private => MsFlags::MS_PRIVATE,
+        }
+    }
+}
+
+/// Change the propagation type of the existing mount at `path`.
+///
+/// The mountpoint itself is modified in place, no new mount is created, so `path` must already
+/// be a mountpoint.
+pub fn set_propagation(path: &str, flag: PropagationFlag) -> Result<()> {
+    let path = Path::new(path);
+    if path.is_empty() {
+        return Err(Error::NullMountPointPath);
+    }
+    let path = path
+        .canonicalize()
+        .map_err(|_e| Error::InvalidPath(path.to_path_buf()))?;
+
+    mount(
+        Some(""),
+        &path,
+        Some(""),
+        MsFlags::from(flag),
+        Some(""),
+    )
+    .map_err(|e| Error::Remount(path, e))
+}
+
 /// Remount a bind mount
 ///
 /// # Safety
@@ -811,6 +855,36 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_set_propagation() {
+        use test_utils::skip_if_not_root;
+
+        assert!(matches!(
+            set_propagation("", PropagationFlag::Private),
+            Err(Error::NullMountPointPath)
+        ));
+        assert!(matches!(
+            set_propagation("/___does_not_exist____a___", PropagationFlag::Private),
+            Err(Error::InvalidPath(_))
+        ));
+
+        skip_if_not_root!();
+
+        let tmpdir = tempfile::tempdir().unwrap();
+        let path = tmpdir.path().to_str().unwrap();
+        bind_mount_unchecked(tmpdir.path(), tmpdir.path(), false, MsFlags::MS_SLAVE).unwrap();
+
+        for flag in [
+            PropagationFlag::Shared,
+            PropagationFlag::Slave,
+            PropagationFlag::Private,
+        ] {
+            set_propagation(path, flag).unwrap();
+        }
+
+        umount_all(tmpdir.path(), true).unwrap();
+    }
+
     #[test]
     fn test_create_mount_destination() {
         let tmpdir = tempfile::tempdir().unwrap();
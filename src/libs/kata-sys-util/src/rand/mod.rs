@@ -8,3 +8,44 @@ mod random_bytes;
 pub use random_bytes::RandomBytes;
 mod uuid;
 pub use uuid::UUID;
+
+/// Generate a random UUID v4 string, e.g. "00010203-0405-4607-8809-0a0b0c0d0e0f".
+pub fn uuid_v4() -> String {
+    String::from(&UUID::new())
+}
+
+/// Generate a random id of `len` lower-hex characters.
+///
+/// Device managers use this to generate collision-resistant device ids that still fit within
+/// length limits such as `MAX_DEV_ID_SIZE`, where a full UUID would be truncated.
+pub fn short_id(len: usize) -> String {
+    let bytes = RandomBytes::new(len.div_ceil(2));
+    let mut id: String = bytes.bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    id.truncate(len);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uuid_v4() {
+        let id1 = uuid_v4();
+        let id2 = uuid_v4();
+
+        assert_eq!(id1.len(), 36);
+        assert_ne!(id1, id2);
+        // version and variant nibbles
+        assert_eq!(&id1[14..15], "4");
+        assert!(matches!(&id1[19..20], "8" | "9" | "a" | "b"));
+    }
+
+    #[test]
+    fn test_short_id() {
+        assert_eq!(short_id(0).len(), 0);
+        assert_eq!(short_id(7).len(), 7);
+        assert_eq!(short_id(31).len(), 31);
+        assert_ne!(short_id(16), short_id(16));
+    }
+}
@@ -80,6 +80,37 @@ pub fn get_shim_id_info() -> Result<ShimIdInfo, Error> {
     }
 }
 
+/// Get the guest hooks (prestart/poststart/poststop) from an OCI spec.
+///
+/// Hook paths are normalized by [`Path::components`](std::path::Path::components) and must be
+/// absolute; hooks with relative paths are dropped. Whether the hook binary actually exists is
+/// checked by the consumer at execution time, not here.
+pub fn get_guest_hooks(spec: &oci::Spec) -> Vec<oci::Hook> {
+    let mut guest_hooks = Vec::new();
+    if let Some(hooks) = spec.hooks.as_ref() {
+        for hook in hooks
+            .prestart
+            .iter()
+            .chain(hooks.poststart.iter())
+            .chain(hooks.poststop.iter())
+        {
+            let path = PathBuf::from(&hook.path);
+            if !path.is_absolute() {
+                continue;
+            }
+            let mut hook = hook.clone();
+            hook.path = path
+                .components()
+                .collect::<PathBuf>()
+                .to_string_lossy()
+                .into_owned();
+            guest_hooks.push(hook);
+        }
+    }
+
+    guest_hooks
+}
+
 /// get bundle path
 pub fn get_bundle_path() -> std::io::Result<PathBuf> {
     std::env::current_dir()
@@ -92,3 +123,31 @@ pub fn load_oci_spec() -> oci::Result<oci::Spec> {
 
     oci::Spec::load(spec_file.to_str().unwrap_or_default())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_guest_hooks() {
+        let mut spec = oci::Spec::default();
+        assert!(get_guest_hooks(&spec).is_empty());
+
+        let hook = |path: &str| oci::Hook {
+            path: path.to_string(),
+            ..Default::default()
+        };
+        spec.hooks = Some(oci::Hooks {
+            prestart: vec![hook("/usr/bin/prestart"), hook("relative/prestart")],
+            poststart: vec![hook("/usr/bin//poststart")],
+            poststop: vec![hook("/usr/bin/poststop")],
+            ..Default::default()
+        });
+
+        let hooks = get_guest_hooks(&spec);
+        assert_eq!(hooks.len(), 3);
+        assert_eq!(hooks[0].path, "/usr/bin/prestart");
+        assert_eq!(hooks[1].path, "/usr/bin/poststart");
+        assert_eq!(hooks[2].path, "/usr/bin/poststop");
+    }
+}
@@ -930,6 +930,14 @@ pub struct SharedFsInfo {
     /// This is the msize used for 9p shares. It is the number of bytes used for 9p packet payload.
     #[serde(default)]
     pub msize_9p: u32,
+
+    /// Directory used as the blob cache working directory for nydus rootfs.
+    ///
+    /// An empty value keeps the working directory from the snapshotter's rafs
+    /// configuration, a non-empty value overrides it, e.g. to point the blob
+    /// cache at faster storage.
+    #[serde(default)]
+    pub nydus_blob_cache_dir: String,
 }
 
 impl SharedFsInfo {
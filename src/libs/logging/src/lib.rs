@@ -189,6 +189,31 @@ pub fn register_subsystem_logger(component_name: &str, subsystem_name: &str) {
     });
 }
 
+/// Set a runtime log level override for a single subsystem.
+///
+/// Records tagged with `slog::o!("subsystem" => ...)` matching `subsystem_name`
+/// are filtered against `level` instead of the global log level, so verbose
+/// logging can be confined to one component.
+pub fn set_subsystem_log_level(subsystem_name: &str, level: slog::Level) {
+    FILTER_RULE.rcu(|inner| {
+        let mut updated_inner = HashMap::new();
+        updated_inner.clone_from(inner);
+        updated_inner.insert(subsystem_name.to_string(), level);
+        updated_inner
+    });
+}
+
+/// Remove a runtime log level override for a single subsystem, so its records
+/// are filtered against the global log level again.
+pub fn clear_subsystem_log_level(subsystem_name: &str) {
+    FILTER_RULE.rcu(|inner| {
+        let mut updated_inner = HashMap::new();
+        updated_inner.clone_from(inner);
+        updated_inner.remove(subsystem_name);
+        updated_inner
+    });
+}
+
 // Used to convert an slog::OwnedKVList into a hash map.
 #[derive(Debug)]
 struct HashSerializer {
@@ -318,20 +343,29 @@ where
             .expect("log record serialization failed");
 
         let mut component = None;
+        let mut subsystem = None;
         for (k, v) in record_serializer
             .fields
             .iter()
             .chain(logger_serializer.fields.iter())
         {
-            if k == "component" {
+            if k == "component" && component.is_none() {
                 component = Some(v.to_string());
-                break;
+            } else if k == "subsystem" && subsystem.is_none() {
+                subsystem = Some(v.to_string());
             }
         }
-        let according_level = component_level_config
-            .get(&component.unwrap_or(DEFAULT_SUBSYSTEM.to_string()))
-            .unwrap_or(&self.log_level);
-        if record.level().is_at_least(*according_level) {
+        // A subsystem level override takes precedence over the component one,
+        // which in turn takes precedence over the global log level.
+        let according_level = subsystem
+            .and_then(|s| component_level_config.get(&s).copied())
+            .or_else(|| {
+                component_level_config
+                    .get(&component.unwrap_or(DEFAULT_SUBSYSTEM.to_string()))
+                    .copied()
+            })
+            .unwrap_or(self.log_level);
+        if record.level().is_at_least(according_level) {
             self.drain.log(record, values)?;
         }
 
@@ -560,6 +594,46 @@ mod tests {
         assert_eq!(field_record_value, record_value);
     }
 
+    #[test]
+    fn test_subsystem_log_level_override() {
+        let writer = NamedTempFile::new().expect("failed to create tempfile");
+        let mut writer_ref = writer.reopen().expect("failed to clone tempfile");
+
+        let noisy_msg = "debug message allowed by the subsystem override";
+        let blocked_msg = "debug message blocked by the global level";
+        let quiet_msg = "info message blocked by the subsystem override";
+
+        // Global level Info: debug records are dropped, info records pass.
+        let (logger, guard) = create_logger("name", "source", slog::Level::Info, writer);
+
+        set_subsystem_log_level("test-sub-noisy", slog::Level::Debug);
+        set_subsystem_log_level("test-sub-quiet", slog::Level::Error);
+
+        // Below the global level, but allowed by the subsystem override.
+        debug!(&logger, "{}", noisy_msg; "subsystem" => "test-sub-noisy");
+        // Below the global level, and no override for this subsystem.
+        debug!(&logger, "{}", blocked_msg; "subsystem" => "test-sub-other");
+        // Above the global level, but blocked by the subsystem override.
+        info!(&logger, "{}", quiet_msg; "subsystem" => "test-sub-quiet");
+
+        // Force temp file to be flushed
+        drop(guard);
+        drop(logger);
+
+        clear_subsystem_log_level("test-sub-noisy");
+        clear_subsystem_log_level("test-sub-quiet");
+        assert!(!FILTER_RULE.load().contains_key("test-sub-noisy"));
+
+        let mut contents = String::new();
+        writer_ref
+            .read_to_string(&mut contents)
+            .expect("failed to read tempfile contents");
+
+        assert!(contents.contains(noisy_msg));
+        assert!(!contents.contains(blocked_msg));
+        assert!(!contents.contains(quiet_msg));
+    }
+
     #[test]
     fn test_logger_levels() {
         let name = "name";
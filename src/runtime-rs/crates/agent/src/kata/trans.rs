@@ -23,8 +23,9 @@ use crate::{
         ReadStreamRequest, ReadStreamResponse, RemoveContainerRequest, ReseedRandomDevRequest,
         ResizeVolumeRequest, Route, Routes, SetGuestDateTimeRequest, SetIPTablesRequest,
         SetIPTablesResponse, SharedMount, SignalProcessRequest, StatsContainerResponse, Storage,
-        StringUser, ThrottlingData, TtyWinResizeRequest, UpdateContainerRequest,
-        UpdateInterfaceRequest, UpdateRoutesRequest, VersionCheckResponse, VolumeStatsRequest,
+        StringUser, ThrottlingData, TtyWinResizeRequest, UpdateAgentConfigRequest,
+        UpdateContainerRequest, UpdateInterfaceRequest, UpdateRoutesRequest, VersionCheckResponse,
+        VolumeStatsRequest,
         VolumeStatsResponse, WaitProcessRequest, WriteStreamRequest,
     },
     GetGuestDetailsRequest, OomEventResponse, WaitProcessResponse, WriteStreamResponse,
@@ -729,6 +730,15 @@ impl From<SetGuestDateTimeRequest> for agent::SetGuestDateTimeRequest {
     }
 }
 
+impl From<UpdateAgentConfigRequest> for agent::UpdateAgentConfigRequest {
+    fn from(from: UpdateAgentConfigRequest) -> Self {
+        Self {
+            log_level: from.log_level,
+            ..Default::default()
+        }
+    }
+}
+
 impl From<GetGuestDetailsRequest> for agent::GuestDetailsRequest {
     fn from(from: GetGuestDetailsRequest) -> Self {
         Self {
@@ -22,8 +22,9 @@ pub use types::{
     OnlineCPUMemRequest, OomEventResponse, ReadStreamRequest, ReadStreamResponse,
     RemoveContainerRequest, ReseedRandomDevRequest, ResizeVolumeRequest, Route, Routes,
     SetGuestDateTimeRequest, SetIPTablesRequest, SetIPTablesResponse, SignalProcessRequest,
-    StatsContainerResponse, Storage, TtyWinResizeRequest, UpdateContainerRequest,
-    UpdateInterfaceRequest, UpdateRoutesRequest, VersionCheckResponse, VolumeStatsRequest,
+    StatsContainerResponse, Storage, TtyWinResizeRequest, UpdateAgentConfigRequest,
+    UpdateContainerRequest, UpdateInterfaceRequest, UpdateRoutesRequest, VersionCheckResponse,
+    VolumeStatsRequest,
     VolumeStatsResponse, WaitProcessRequest, WaitProcessResponse, WriteStreamRequest,
     WriteStreamResponse,
 };
@@ -56,6 +57,7 @@ pub trait Agent: AgentManager + HealthService + Send + Sync {
     async fn create_sandbox(&self, req: CreateSandboxRequest) -> Result<Empty>;
     async fn destroy_sandbox(&self, req: Empty) -> Result<Empty>;
     async fn online_cpu_mem(&self, req: OnlineCPUMemRequest) -> Result<Empty>;
+    async fn update_agent_config(&self, req: UpdateAgentConfigRequest) -> Result<Empty>;
 
     // network
     async fn add_arp_neighbors(&self, req: AddArpNeighborRequest) -> Result<Empty>;
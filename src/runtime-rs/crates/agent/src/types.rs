@@ -520,6 +520,11 @@ pub struct SetGuestDateTimeRequest {
     pub usec: i64,
 }
 
+#[derive(PartialEq, Clone, Default)]
+pub struct UpdateAgentConfigRequest {
+    pub log_level: String,
+}
+
 #[derive(PartialEq, Clone, Default)]
 pub struct AgentDetails {
     pub version: String,
@@ -489,6 +489,8 @@ mod tests {
             allow_duplicate_mac: false,
             use_generic_irq: None,
             use_shared_irq: None,
+            rx_rate_limiter: None,
+            tx_rate_limiter: None,
         };
 
         let net = NetConfig::try_from(cfg.clone());
@@ -14,8 +14,9 @@ use tokio::sync::{Mutex, RwLock};
 use crate::{
     vhost_user_blk::VhostUserBlkDevice, BlockConfig, BlockDevice, HybridVsockDevice, Hypervisor,
     NetworkDevice, ShareFsDevice, VfioDevice, VhostUserConfig, VhostUserNetDevice, VsockDevice,
-    KATA_BLK_DEV_TYPE, KATA_CCW_DEV_TYPE, KATA_MMIO_BLK_DEV_TYPE, KATA_NVDIMM_DEV_TYPE,
-    VIRTIO_BLOCK_CCW, VIRTIO_BLOCK_MMIO, VIRTIO_BLOCK_PCI, VIRTIO_PMEM,
+    VsockDeviceError, KATA_BLK_DEV_TYPE, KATA_CCW_DEV_TYPE, KATA_MMIO_BLK_DEV_TYPE,
+    KATA_NVDIMM_DEV_TYPE, MAX_DEV_ID_SIZE, VIRTIO_BLOCK_CCW, VIRTIO_BLOCK_MMIO, VIRTIO_BLOCK_PCI,
+    VIRTIO_PMEM,
 };
 
 use super::{
@@ -28,12 +29,15 @@ pub type ArcMutexDevice = Arc<Mutex<dyn Device>>;
 
 macro_rules! declare_index {
     ($self:ident, $index:ident, $released_index:ident) => {{
+        // prefer reusing a released index so that device indices/names don't
+        // get exhausted by repeated attach/detach cycles.
         let current_index = if let Some(index) = $self.$released_index.pop() {
             index
         } else {
-            $self.$index
+            let index = $self.$index;
+            $self.$index += 1;
+            index
         };
-        $self.$index += 1;
         Ok(current_index)
     }};
 }
@@ -250,7 +254,12 @@ impl DeviceManager {
                         return Some(device_id.to_string());
                     }
                 }
-                DeviceType::HybridVsock(_) | DeviceType::Vsock(_) => {
+                DeviceType::HybridVsock(device) => {
+                    if device.config.uds_path == host_path {
+                        return Some(device_id.to_string());
+                    }
+                }
+                DeviceType::Vsock(_) => {
                     continue;
                 }
             }
@@ -361,7 +370,13 @@ impl DeviceManager {
                 )))
             }
             DeviceConfig::HybridVsockCfg(hvconfig) => {
-                // No need to do find device for hybrid vsock device.
+                // the uds path must be absolute and unique within this sandbox, two
+                // vsock devices sharing one uds path would clobber each other.
+                hvconfig.validate()?;
+                if self.find_device(hvconfig.uds_path.clone()).await.is_some() {
+                    return Err(VsockDeviceError::UdsPathInUse(hvconfig.uds_path.clone()).into());
+                }
+
                 Arc::new(Mutex::new(HybridVsockDevice::new(&device_id, hvconfig)))
             }
             DeviceConfig::VsockCfg(vconfig) => {
@@ -389,11 +404,31 @@ impl DeviceManager {
         };
 
         // register device to devices
+        self.validate_device_id(&device_id)?;
         self.devices.insert(device_id.clone(), dev.clone());
 
         Ok(device_id)
     }
 
+    // Device ids end up as identifiers on the hypervisor command line and in
+    // the guest, so enforce the size limit and uniqueness at registration
+    // time no matter where the id came from.
+    fn validate_device_id(&self, device_id: &str) -> Result<()> {
+        if device_id.is_empty() || device_id.len() > MAX_DEV_ID_SIZE {
+            return Err(anyhow!(
+                "device id {:?} is empty or longer than {} characters",
+                device_id,
+                MAX_DEV_ID_SIZE
+            ));
+        }
+
+        if self.devices.contains_key(device_id) {
+            return Err(anyhow!("device id {} is already registered", device_id));
+        }
+
+        Ok(())
+    }
+
     async fn create_vhost_blk_device(
         &mut self,
         config: &VhostUserConfig,
@@ -589,6 +624,42 @@ pub async fn do_handle_device(
     Ok(device_info)
 }
 
+/// Create and attach a batch of devices while holding the device manager
+/// lock only once, instead of re-acquiring it for every device as
+/// `do_handle_device()` does. One result is returned per config, in the
+/// same order; a failing device does not abort the rest of the batch.
+pub async fn do_handle_devices(
+    d: &RwLock<DeviceManager>,
+    dev_infos: &[DeviceConfig],
+) -> Vec<Result<DeviceType>> {
+    let mut dm = d.write().await;
+
+    let mut results = Vec::with_capacity(dev_infos.len());
+    for dev_info in dev_infos {
+        let result = match dm
+            .new_device(dev_info)
+            .await
+            .context("failed to create device")
+        {
+            Ok(device_id) => match dm
+                .try_add_device(&device_id)
+                .await
+                .context("failed to add deivce")
+            {
+                Ok(()) => dm
+                    .get_device_info(&device_id)
+                    .await
+                    .context("failed to get device info"),
+                Err(e) => Err(e),
+            },
+            Err(e) => Err(e),
+        };
+        results.push(result);
+    }
+
+    results
+}
+
 pub async fn do_update_device(
     d: &RwLock<DeviceManager>,
     updated_config: &DeviceConfig,
@@ -608,11 +679,14 @@ pub async fn get_block_driver(d: &RwLock<DeviceManager>) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::DeviceManager;
+    use super::{DeviceManager, SharedInfo};
     use crate::{
-        device::{device_manager::get_block_driver, DeviceConfig, DeviceType},
+        device::{
+            device_manager::{do_handle_devices, get_block_driver},
+            DeviceConfig, DeviceType,
+        },
         qemu::Qemu,
-        BlockConfig, KATA_BLK_DEV_TYPE,
+        BlockConfig, HybridVsockConfig, KATA_BLK_DEV_TYPE, MAX_DEV_ID_SIZE,
     };
     use anyhow::{anyhow, Context, Result};
     use kata_types::config::hypervisor::TopologyConfigInfo;
@@ -643,6 +717,224 @@ mod tests {
         Ok(dm)
     }
 
+    #[actix_rt::test]
+    async fn test_device_index_recycling() {
+        let mut shared_info = SharedInfo::new().await;
+
+        // attach three block devices
+        assert_eq!(shared_info.declare_device_index(false).unwrap(), 0);
+        assert_eq!(shared_info.declare_device_index(false).unwrap(), 1);
+        assert_eq!(shared_info.declare_device_index(false).unwrap(), 2);
+
+        // detach the middle one, its index must be reused for the next attach
+        shared_info.release_device_index(1, false);
+        assert_eq!(shared_info.declare_device_index(false).unwrap(), 1);
+
+        // with no released indices left, allocation continues from the top
+        assert_eq!(shared_info.declare_device_index(false).unwrap(), 3);
+
+        // the lowest released index is always reused first
+        shared_info.release_device_index(2, false);
+        shared_info.release_device_index(0, false);
+        assert_eq!(shared_info.declare_device_index(false).unwrap(), 0);
+        assert_eq!(shared_info.declare_device_index(false).unwrap(), 2);
+
+        // pmem indices are tracked independently of block indices
+        assert_eq!(shared_info.declare_device_index(true).unwrap(), 0);
+    }
+
+    #[actix_rt::test]
+    async fn test_try_remove_device_frees_drive_index() {
+        use crate::device::topology::PCIeTopology;
+        use crate::device::Device;
+        use crate::BlockDevice;
+        use crate::Hypervisor as hypervisor;
+        use async_trait::async_trait;
+        use tokio::sync::Mutex;
+
+        // stub device reporting its drive index as freed on detach
+        #[derive(Debug)]
+        struct StubDevice {
+            index: u64,
+        }
+
+        #[async_trait]
+        impl Device for StubDevice {
+            async fn attach(
+                &mut self,
+                _pcie_topo: &mut Option<&mut PCIeTopology>,
+                _h: &dyn hypervisor,
+            ) -> Result<()> {
+                Ok(())
+            }
+            async fn detach(
+                &mut self,
+                _pcie_topo: &mut Option<&mut PCIeTopology>,
+                _h: &dyn hypervisor,
+            ) -> Result<Option<u64>> {
+                Ok(Some(self.index))
+            }
+            async fn update(&mut self, _h: &dyn hypervisor) -> Result<()> {
+                Ok(())
+            }
+            async fn get_device_info(&self) -> DeviceType {
+                DeviceType::Block(BlockDevice::new(
+                    "stub".to_owned(),
+                    BlockConfig {
+                        index: self.index,
+                        ..Default::default()
+                    },
+                ))
+            }
+            async fn increase_attach_count(&mut self) -> Result<bool> {
+                Ok(false)
+            }
+            async fn decrease_attach_count(&mut self) -> Result<bool> {
+                Ok(false)
+            }
+        }
+
+        let dm = new_device_manager().await.unwrap();
+        let mut dm = dm.write().await;
+
+        // declare an index the way the block device creation path would
+        let index = dm.shared_info.declare_device_index(false).unwrap();
+        dm.devices
+            .insert("stub".to_owned(), Arc::new(Mutex::new(StubDevice { index })));
+
+        dm.try_remove_device("stub").await.unwrap();
+        assert!(dm.devices.get("stub").is_none());
+
+        // the freed index must be handed out again by the allocator
+        assert_eq!(dm.shared_info.declare_device_index(false).unwrap(), index);
+    }
+
+    #[actix_rt::test]
+    async fn test_new_hybrid_vsock_device_uds_path_collision() {
+        let dm = new_device_manager().await;
+        assert!(dm.is_ok());
+
+        let d = dm.unwrap();
+        let dev_info = DeviceConfig::HybridVsockCfg(HybridVsockConfig {
+            guest_cid: 3,
+            uds_path: "/tmp/kata-test-vsock.sock".to_string(),
+        });
+
+        // first registration with a unique path succeeds
+        let new_device_result = d.write().await.new_device(&dev_info).await;
+        assert!(new_device_result.is_ok());
+
+        // a second device with the same uds path is rejected
+        let new_device_result = d.write().await.new_device(&dev_info).await;
+        assert!(new_device_result.is_err());
+
+        // a relative uds path is rejected
+        let dev_info = DeviceConfig::HybridVsockCfg(HybridVsockConfig {
+            guest_cid: 3,
+            uds_path: "relative/vsock.sock".to_string(),
+        });
+        let new_device_result = d.write().await.new_device(&dev_info).await;
+        assert!(new_device_result.is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_new_block_device_readonly() {
+        let dm = new_device_manager().await;
+        assert!(dm.is_ok());
+
+        let d = dm.unwrap();
+        let block_driver = get_block_driver(&d).await;
+        let dev_info = DeviceConfig::BlockCfg(BlockConfig {
+            path_on_host: "/dev/dddzzz".to_string(),
+            driver_option: block_driver,
+            is_readonly: true,
+            ..Default::default()
+        });
+        let device_id = d.write().await.new_device(&dev_info).await.unwrap();
+
+        // the read-only flag must survive device creation so that the
+        // hypervisor sees it at attach time.
+        let device_info = d.read().await.get_device_info(&device_id).await.unwrap();
+        if let DeviceType::Block(device) = device_info {
+            assert!(device.config.is_readonly);
+        } else {
+            panic!("unexpected device type");
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_validate_device_id() {
+        let dm = new_device_manager().await;
+        assert!(dm.is_ok());
+
+        let d = dm.unwrap();
+
+        // ids up to the limit are accepted, over-long and empty ids are not
+        let max_id = "x".repeat(MAX_DEV_ID_SIZE);
+        assert!(d.read().await.validate_device_id(&max_id).is_ok());
+        let long_id = "x".repeat(MAX_DEV_ID_SIZE + 1);
+        assert!(d.read().await.validate_device_id(&long_id).is_err());
+        assert!(d.read().await.validate_device_id("").is_err());
+
+        // an id already registered in the manager is rejected
+        let block_driver = get_block_driver(&d).await;
+        let dev_info = DeviceConfig::BlockCfg(BlockConfig {
+            path_on_host: "/dev/dupcheck".to_string(),
+            driver_option: block_driver,
+            ..Default::default()
+        });
+        let device_id = d.write().await.new_device(&dev_info).await.unwrap();
+        assert!(d.read().await.validate_device_id(&device_id).is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_do_handle_devices_batch() {
+        let dm = new_device_manager().await;
+        assert!(dm.is_ok());
+
+        let d = dm.unwrap();
+        let block_driver = get_block_driver(&d).await;
+        let dev_infos = vec![
+            DeviceConfig::BlockCfg(BlockConfig {
+                path_on_host: "/dev/batcha".to_string(),
+                driver_option: block_driver.clone(),
+                ..Default::default()
+            }),
+            DeviceConfig::BlockCfg(BlockConfig {
+                path_on_host: "/dev/batchb".to_string(),
+                driver_option: block_driver,
+                ..Default::default()
+            }),
+            DeviceConfig::HybridVsockCfg(HybridVsockConfig {
+                guest_cid: 3,
+                uds_path: "/tmp/kata-test-batch-vsock.sock".to_string(),
+            }),
+            // duplicate uds path, must fail without aborting the batch
+            DeviceConfig::HybridVsockCfg(HybridVsockConfig {
+                guest_cid: 3,
+                uds_path: "/tmp/kata-test-batch-vsock.sock".to_string(),
+            }),
+        ];
+
+        let results = do_handle_devices(&d, &dev_infos).await;
+        assert_eq!(results.len(), dev_infos.len());
+
+        // each block device got its own index
+        if let Ok(DeviceType::Block(device)) = &results[0] {
+            assert_eq!(device.config.index, 0);
+        } else {
+            panic!("unexpected result for first block device");
+        }
+        if let Ok(DeviceType::Block(device)) = &results[1] {
+            assert_eq!(device.config.index, 1);
+        } else {
+            panic!("unexpected result for second block device");
+        }
+
+        assert!(results[2].is_ok());
+        assert!(results[3].is_err());
+    }
+
     #[actix_rt::test]
     async fn test_new_block_device() {
         let dm = new_device_manager().await;
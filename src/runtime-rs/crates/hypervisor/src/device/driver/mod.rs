@@ -15,7 +15,7 @@ mod virtio_vsock;
 
 pub use vfio::{
     bind_device_to_host, bind_device_to_vfio, get_vfio_device, HostDevice, VfioBusMode, VfioConfig,
-    VfioDevice,
+    VfioDevice, MAX_DEV_ID_SIZE,
 };
 pub use vhost_user::{VhostUserConfig, VhostUserDevice, VhostUserType};
 pub use vhost_user_net::VhostUserNetDevice;
@@ -28,5 +28,6 @@ pub use virtio_fs::{
 };
 pub use virtio_net::{Address, NetworkConfig, NetworkDevice};
 pub use virtio_vsock::{
-    HybridVsockConfig, HybridVsockDevice, VsockConfig, VsockDevice, DEFAULT_GUEST_VSOCK_CID,
+    allocate_guest_cid, free_guest_cid, HybridVsockConfig, HybridVsockDevice, VsockConfig,
+    VsockDevice, VsockDeviceError, DEFAULT_GUEST_VSOCK_CID,
 };
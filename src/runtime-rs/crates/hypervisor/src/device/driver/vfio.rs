@@ -178,7 +178,7 @@ pub struct HostDevice {
 }
 
 // VfioConfig represents a VFIO drive used for hotplugging
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct VfioConfig {
     /// usually host path will be /dev/vfio/N
     pub host_path: String,
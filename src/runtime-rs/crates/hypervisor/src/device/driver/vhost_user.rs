@@ -6,7 +6,7 @@
 
 use crate::device::pci_path::PciPath;
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum VhostUserType {
     /// Blk - represents a block vhostuser device type
     #[default]
@@ -22,7 +22,7 @@ pub enum VhostUserType {
     FS,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 /// VhostUserConfig represents data shared by most vhost-user devices
 pub struct VhostUserConfig {
     /// device id
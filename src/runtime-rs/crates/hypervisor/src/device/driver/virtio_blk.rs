@@ -11,7 +11,7 @@ use crate::device::util::do_increase_count;
 use crate::device::Device;
 use crate::device::DeviceType;
 use crate::Hypervisor as hypervisor;
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 
 /// VIRTIO_BLOCK_PCI indicates block driver is virtio-pci based
@@ -24,7 +24,7 @@ pub const KATA_BLK_DEV_TYPE: &str = "blk";
 pub const KATA_CCW_DEV_TYPE: &str = "ccw";
 pub const KATA_NVDIMM_DEV_TYPE: &str = "nvdimm";
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct BlockConfig {
     /// Path of the drive.
     pub path_on_host: String,
@@ -58,6 +58,33 @@ pub struct BlockConfig {
     pub minor: i64,
 }
 
+impl BlockConfig {
+    /// Resolve the virtio transport for this block device from the requested
+    /// dev type and the transports supported by the hypervisor.
+    ///
+    /// `supported` holds the transport constants the hypervisor can handle,
+    /// e.g. `&[VIRTIO_BLOCK_MMIO]` for dragonball. An error is returned when
+    /// the requested dev type is unknown or maps to an unsupported transport.
+    pub fn resolve_transport(&self, supported: &[&str]) -> Result<&'static str> {
+        let transport = match self.driver_option.as_str() {
+            KATA_MMIO_BLK_DEV_TYPE => VIRTIO_BLOCK_MMIO,
+            KATA_BLK_DEV_TYPE => VIRTIO_BLOCK_PCI,
+            KATA_CCW_DEV_TYPE => VIRTIO_BLOCK_CCW,
+            KATA_NVDIMM_DEV_TYPE => VIRTIO_PMEM,
+            _ => return Err(anyhow!("unknown block device type {}", self.driver_option)),
+        };
+
+        if !supported.contains(&transport) {
+            return Err(anyhow!(
+                "block device transport {} is not supported by the hypervisor",
+                transport
+            ));
+        }
+
+        Ok(transport)
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct BlockDevice {
     pub device_id: String,
@@ -144,3 +171,64 @@ impl Device for BlockDevice {
         do_decrease_count(&mut self.attach_count)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_transport() {
+        let mmio_config = BlockConfig {
+            driver_option: KATA_MMIO_BLK_DEV_TYPE.to_string(),
+            ..Default::default()
+        };
+        let pci_config = BlockConfig {
+            driver_option: KATA_BLK_DEV_TYPE.to_string(),
+            ..Default::default()
+        };
+
+        // MMIO-only hypervisor, e.g. dragonball.
+        let supported = [VIRTIO_BLOCK_MMIO];
+        assert_eq!(
+            mmio_config.resolve_transport(&supported).unwrap(),
+            VIRTIO_BLOCK_MMIO
+        );
+        assert!(pci_config.resolve_transport(&supported).is_err());
+
+        // PCI-only hypervisor.
+        let supported = [VIRTIO_BLOCK_PCI];
+        assert_eq!(
+            pci_config.resolve_transport(&supported).unwrap(),
+            VIRTIO_BLOCK_PCI
+        );
+        assert!(mmio_config.resolve_transport(&supported).is_err());
+
+        // Unknown dev type is always rejected.
+        let unknown_config = BlockConfig {
+            driver_option: "floppy".to_string(),
+            ..Default::default()
+        };
+        assert!(unknown_config
+            .resolve_transport(&[VIRTIO_BLOCK_MMIO, VIRTIO_BLOCK_PCI])
+            .is_err());
+    }
+
+    #[actix_rt::test]
+    async fn test_attach_count_errors_at_max() {
+        let mut device = BlockDevice::new("blk-count-test".to_string(), BlockConfig::default());
+
+        // normal reference counting semantics
+        assert!(!device.increase_attach_count().await.unwrap());
+        assert!(device.increase_attach_count().await.unwrap());
+        assert_eq!(device.attach_count, 2);
+
+        // the counter must error at u64::MAX instead of wrapping around
+        device.attach_count = u64::MAX;
+        assert!(device.increase_attach_count().await.is_err());
+        assert_eq!(device.attach_count, u64::MAX);
+
+        // decreasing from the max is still possible
+        assert!(device.decrease_attach_count().await.unwrap());
+        assert_eq!(device.attach_count, u64::MAX - 1);
+    }
+}
@@ -9,15 +9,18 @@ use async_trait::async_trait;
 
 use crate::device::{hypervisor, topology::PCIeTopology, Device, DeviceType};
 
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub enum ShareFsMountOperation {
     #[default]
     Mount,
     Umount,
     Update,
+    /// Remount an existing mount point with new options in one step, instead
+    /// of racing an umount/mount pair.
+    Remount,
 }
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq)]
 pub enum ShareFsMountType {
     #[default]
     PASSTHROUGH,
@@ -25,7 +28,7 @@ pub enum ShareFsMountType {
 }
 
 /// ShareFsMountConfig: share fs mount config
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ShareFsMountConfig {
     /// source: the passthrough fs exported dir or rafs meta file of rafs
     pub source: String,
@@ -51,7 +54,7 @@ pub struct ShareFsMountConfig {
 
 /// ShareFsConfig: Sharefs config for virtio-fs devices and their corresponding mount configurations,
 /// facilitating mount/umount/update operations.
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct ShareFsConfig {
     /// host_shared_path: the upperdir of the passthrough fs exported dir or rafs meta file of rafs
     pub host_shared_path: String,
@@ -74,6 +77,9 @@ pub struct ShareFsConfig {
     /// options: virtiofs device's config options.
     pub options: Vec<String>,
 
+    /// dax_cache_size: DAX cache window size in bytes, `None` or 0 disables DAX.
+    pub dax_cache_size: Option<u64>,
+
     /// mount config for sharefs mount/umount/update
     pub mount_config: Option<ShareFsMountConfig>,
 }
@@ -8,12 +8,13 @@ use std::fmt;
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use dragonball::config_manager::RateLimiterConfigInfo;
 
 use crate::device::topology::PCIeTopology;
 use crate::device::{Device, DeviceType};
 use crate::Hypervisor as hypervisor;
 
-#[derive(Clone, Default)]
+#[derive(Clone, Default, PartialEq)]
 pub struct Address(pub [u8; 6]);
 
 impl fmt::Debug for Address {
@@ -27,7 +28,7 @@ impl fmt::Debug for Address {
     }
 }
 
-#[derive(Clone, Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct NetworkConfig {
     /// for detach, now it's default value 0.
     pub index: u64,
@@ -48,6 +49,10 @@ pub struct NetworkConfig {
     pub use_generic_irq: Option<bool>,
     /// Allow duplicate mac
     pub allow_duplicate_mac: bool,
+    /// Rate limiter for received packets, `None` leaves the rx path unlimited.
+    pub rx_rate_limiter: Option<RateLimiterConfigInfo>,
+    /// Rate limiter for transmitted packets, `None` leaves the tx path unlimited.
+    pub tx_rate_limiter: Option<RateLimiterConfigInfo>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -5,8 +5,11 @@
 //
 
 use anyhow::{Context, Result};
+use lazy_static::lazy_static;
 use rand::Rng;
+use std::collections::HashSet;
 use std::os::unix::prelude::AsRawFd;
+use std::sync::Mutex;
 use tokio::fs::{File, OpenOptions};
 
 use async_trait::async_trait;
@@ -20,7 +23,49 @@ use crate::{
 // can use the same ID, since it's only used in the guest.
 pub const DEFAULT_GUEST_VSOCK_CID: u32 = 0x3;
 
-#[derive(Clone, Debug, Default)]
+#[derive(thiserror::Error, Debug, PartialEq)]
+pub enum VsockDeviceError {
+    #[error("hybrid vsock uds path {0} is not absolute")]
+    UdsPathNotAbsolute(String),
+
+    #[error("hybrid vsock uds path {0} is already in use by another vsock device")]
+    UdsPathInUse(String),
+
+    #[error("guest CID {0} is reserved for the hypervisor and the host")]
+    CidReserved(u32),
+
+    #[error("guest CID {0} is already allocated to another sandbox")]
+    CidInUse(u32),
+}
+
+lazy_static! {
+    // Guest CIDs handed out to sandboxes of this process. The vhost-vsock
+    // kernel driver arbitrates CIDs across the whole host, but hybrid vsock
+    // has no such arbiter, so keep a process wide registry to avoid two
+    // sandboxes being assigned the same CID.
+    static ref ALLOCATED_CIDS: Mutex<HashSet<u32>> = Mutex::new(HashSet::new());
+}
+
+/// Reserve a guest CID for a sandbox, rejecting reserved and duplicate CIDs.
+pub fn allocate_guest_cid(cid: u32) -> Result<u32, VsockDeviceError> {
+    if cid < DEFAULT_GUEST_VSOCK_CID {
+        return Err(VsockDeviceError::CidReserved(cid));
+    }
+
+    let mut cids = ALLOCATED_CIDS.lock().unwrap();
+    if !cids.insert(cid) {
+        return Err(VsockDeviceError::CidInUse(cid));
+    }
+
+    Ok(cid)
+}
+
+/// Release a guest CID on sandbox teardown so it can be reused.
+pub fn free_guest_cid(cid: u32) {
+    ALLOCATED_CIDS.lock().unwrap().remove(&cid);
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct HybridVsockConfig {
     /// A 32-bit Context Identifier (CID) used to identify the guest.
     pub guest_cid: u32,
@@ -29,6 +74,17 @@ pub struct HybridVsockConfig {
     pub uds_path: String,
 }
 
+impl HybridVsockConfig {
+    /// Validate the config, ensuring the uds path is usable.
+    pub fn validate(&self) -> Result<(), VsockDeviceError> {
+        if !self.uds_path.starts_with('/') {
+            return Err(VsockDeviceError::UdsPathNotAbsolute(self.uds_path.clone()));
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Default)]
 pub struct HybridVsockDevice {
     /// Unique identifier of the device
@@ -92,7 +148,7 @@ impl Device for HybridVsockDevice {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct VsockConfig {
     /// A 32-bit Context Identifier (CID) used to identify the guest.
     pub guest_cid: u32,
@@ -222,3 +278,31 @@ pub async fn generate_vhost_vsock_cid() -> Result<(u32, File)> {
         CID_RETRY_COUNT
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allocate_guest_cid() {
+        // reserved CIDs can never be handed out
+        assert_eq!(
+            allocate_guest_cid(0),
+            Err(VsockDeviceError::CidReserved(0))
+        );
+        assert_eq!(
+            allocate_guest_cid(2),
+            Err(VsockDeviceError::CidReserved(2))
+        );
+
+        // a free CID is allocated once and only once
+        let cid = 0x0a0b_0c0d;
+        assert_eq!(allocate_guest_cid(cid), Ok(cid));
+        assert_eq!(allocate_guest_cid(cid), Err(VsockDeviceError::CidInUse(cid)));
+
+        // freeing a CID makes it available again
+        free_guest_cid(cid);
+        assert_eq!(allocate_guest_cid(cid), Ok(cid));
+        free_guest_cid(cid);
+    }
+}
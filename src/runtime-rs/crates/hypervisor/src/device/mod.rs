@@ -14,6 +14,7 @@ use crate::{
 };
 use anyhow::Result;
 use async_trait::async_trait;
+use nix::sys::stat::{self, SFlag};
 
 use self::topology::PCIeTopology;
 
@@ -23,7 +24,12 @@ pub mod pci_path;
 pub mod topology;
 pub mod util;
 
-#[derive(Debug)]
+/// mount type of a plain bind mount
+const MOUNT_BIND_TYPE: &str = "bind";
+/// mount type marking a vfio volume
+const MOUNT_VFIO_VOLUME_TYPE: &str = "vfiovol";
+
+#[derive(Debug, PartialEq)]
 pub enum DeviceConfig {
     BlockCfg(BlockConfig),
     VhostUserBlkCfg(VhostUserConfig),
@@ -35,6 +41,76 @@ pub enum DeviceConfig {
     HybridVsockCfg(HybridVsockConfig),
 }
 
+impl DeviceConfig {
+    /// Return the device class this config belongs to. The config enum is
+    /// already tagged, so unlike classification from raw device markers this
+    /// can never be undefined.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            DeviceConfig::BlockCfg(_) => "block",
+            DeviceConfig::VhostUserBlkCfg(_) => "vhost-user-blk",
+            DeviceConfig::NetworkCfg(_) => "network",
+            DeviceConfig::VhostUserNetworkCfg(_) => "vhost-user-net",
+            DeviceConfig::ShareFsCfg(_) => "share-fs",
+            DeviceConfig::VfioCfg(_) => "vfio",
+            DeviceConfig::VsockCfg(_) => "vsock",
+            DeviceConfig::HybridVsockCfg(_) => "hybrid-vsock",
+        }
+    }
+
+    /// Return a stable identity key for the device config, used to recognize
+    /// two configs referring to the same underlying device before attach.
+    pub fn key(&self) -> String {
+        let id = match self {
+            DeviceConfig::BlockCfg(config) => config.path_on_host.clone(),
+            DeviceConfig::VhostUserBlkCfg(config) => config.socket_path.clone(),
+            DeviceConfig::NetworkCfg(config) => config.host_dev_name.clone(),
+            DeviceConfig::VhostUserNetworkCfg(config) => config.socket_path.clone(),
+            DeviceConfig::ShareFsCfg(config) => config.host_shared_path.clone(),
+            DeviceConfig::VfioCfg(config) => config.host_path.clone(),
+            DeviceConfig::VsockCfg(config) => config.guest_cid.to_string(),
+            DeviceConfig::HybridVsockCfg(config) => config.uds_path.clone(),
+        };
+
+        format!("{}-{}", self.kind(), id)
+    }
+
+    /// Classify an OCI mount into the device config needed to back it: a
+    /// bind mount of a block device node becomes a block config, a vfio
+    /// volume becomes a vfio config and an ephemeral mount becomes a
+    /// share-fs config. A plain bind mount needs no device at all and
+    /// classifies to `None`.
+    pub fn from_oci_mount(m: &oci::Mount) -> Result<Option<DeviceConfig>> {
+        match m.r#type.as_str() {
+            MOUNT_VFIO_VOLUME_TYPE => Ok(Some(DeviceConfig::VfioCfg(VfioConfig {
+                host_path: m.source.clone(),
+                dev_type: "b".to_string(),
+                hostdev_prefix: "vfio_vol".to_owned(),
+                ..Default::default()
+            }))),
+            kata_types::mount::KATA_EPHEMERAL_VOLUME_TYPE => {
+                Ok(Some(DeviceConfig::ShareFsCfg(ShareFsConfig {
+                    host_shared_path: m.source.clone(),
+                    ..Default::default()
+                })))
+            }
+            MOUNT_BIND_TYPE => match stat::stat(m.source.as_str()) {
+                Ok(fstat) if SFlag::from_bits_truncate(fstat.st_mode) == SFlag::S_IFBLK => {
+                    Ok(Some(DeviceConfig::BlockCfg(BlockConfig {
+                        path_on_host: m.source.clone(),
+                        is_readonly: m.options.iter().any(|opt| opt == "ro"),
+                        ..Default::default()
+                    })))
+                }
+                // a plain bind mount is handled by the share fs, no
+                // device has to be attached for it
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum DeviceType {
     Block(BlockDevice),
@@ -90,3 +166,114 @@ pub trait PCIeDevice: std::fmt::Debug + Send + Sync {
     // unregister pcie device from PCIe Topology
     async fn unregister(&mut self, topology: &mut PCIeTopology) -> Result<()>;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_config_equality() {
+        let config = BlockConfig {
+            path_on_host: "/dev/sda".to_string(),
+            is_readonly: false,
+            ..Default::default()
+        };
+
+        let block_cfg1 = DeviceConfig::BlockCfg(config.clone());
+        let block_cfg2 = DeviceConfig::BlockCfg(config.clone());
+        assert_eq!(block_cfg1, block_cfg2);
+        assert_eq!(block_cfg1.key(), block_cfg2.key());
+
+        let block_cfg3 = DeviceConfig::BlockCfg(BlockConfig {
+            path_on_host: "/dev/sdb".to_string(),
+            ..config.clone()
+        });
+        assert_ne!(block_cfg1, block_cfg3);
+        assert_ne!(block_cfg1.key(), block_cfg3.key());
+
+        // Same identity key, but differing in other fields: not equal.
+        let block_cfg4 = DeviceConfig::BlockCfg(BlockConfig {
+            is_readonly: true,
+            ..config
+        });
+        assert_ne!(block_cfg1, block_cfg4);
+        assert_eq!(block_cfg1.key(), block_cfg4.key());
+    }
+
+    #[test]
+    fn test_device_config_from_oci_mount() {
+        let mount = |r#type: &str, source: &str, options: Vec<String>| oci::Mount {
+            destination: "/data".to_string(),
+            r#type: r#type.to_string(),
+            source: source.to_string(),
+            options,
+        };
+
+        // a vfio volume classifies as a vfio config
+        let config = DeviceConfig::from_oci_mount(&mount("vfiovol", "/dev/vfio/7", vec![]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.kind(), "vfio");
+
+        // an ephemeral mount classifies as a share-fs config
+        let config = DeviceConfig::from_oci_mount(&mount("ephemeral", "/tmp/foo", vec![]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(config.kind(), "share-fs");
+
+        // a bind mount of a regular file needs no device
+        assert!(DeviceConfig::from_oci_mount(&mount("bind", "/proc/self/exe", vec![]))
+            .unwrap()
+            .is_none());
+
+        // neither does a non-volume mount type
+        assert!(DeviceConfig::from_oci_mount(&mount("proc", "proc", vec![]))
+            .unwrap()
+            .is_none());
+
+        // a bind mount of a block device node classifies as a block
+        // config; creating the node needs privileges, skip otherwise
+        let tmpdir = tempdir::TempDir::new("from-oci-mount").unwrap();
+        let node = tmpdir.path().join("blk");
+        if nix::sys::stat::mknod(
+            &node,
+            SFlag::S_IFBLK,
+            nix::sys::stat::Mode::from_bits_truncate(0o600),
+            0,
+        )
+        .is_ok()
+        {
+            let source = node.to_str().unwrap().to_string();
+            match DeviceConfig::from_oci_mount(&mount("bind", &source, vec!["ro".to_string()]))
+                .unwrap()
+                .unwrap()
+            {
+                DeviceConfig::BlockCfg(config) => {
+                    assert_eq!(config.path_on_host, source);
+                    assert!(config.is_readonly);
+                }
+                config => panic!("unexpected device config {:?}", config),
+            }
+        }
+    }
+
+    #[test]
+    fn test_device_config_kind() {
+        for (config, kind) in [
+            (DeviceConfig::BlockCfg(BlockConfig::default()), "block"),
+            (
+                DeviceConfig::NetworkCfg(NetworkConfig::default()),
+                "network",
+            ),
+            (DeviceConfig::VfioCfg(VfioConfig::default()), "vfio"),
+            (
+                DeviceConfig::HybridVsockCfg(HybridVsockConfig::default()),
+                "hybrid-vsock",
+            ),
+        ] {
+            assert_eq!(config.kind(), kind);
+            // the identity key is always prefixed with the device class
+            assert!(config.key().starts_with(kind));
+        }
+    }
+}
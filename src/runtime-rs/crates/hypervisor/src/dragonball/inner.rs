@@ -416,6 +416,7 @@ impl DragonballInner {
                     guest_numa_node_id: None,
                     use_shared_irq: None,
                     use_generic_irq: None,
+                    strict_memory_advice: false,
                 })?;
             }
             Ordering::Less => {
@@ -452,9 +453,24 @@ impl DragonballInner {
     }
 
     pub fn set_hypervisor_config(&mut self, config: HypervisorConfig) {
+        if self.is_booted() {
+            // the config is consumed at boot, a running guest would never
+            // observe the change
+            warn!(
+                sl!(),
+                "hypervisor config change after boot has no effect, ignored"
+            );
+            return;
+        }
         self.config = config;
     }
 
+    /// Whether the vmm instance has started booting; the hypervisor config
+    /// can only be changed before that.
+    pub fn is_booted(&self) -> bool {
+        self.state != VmmState::NotReady
+    }
+
     pub fn hypervisor_config(&self) -> HypervisorConfig {
         self.config.clone()
     }
@@ -17,6 +17,7 @@ use dragonball::device_manager::{
     blk_dev_mgr::BlockDeviceType,
     vfio_dev_mgr::{HostDeviceConfig, VfioPciDeviceConfig},
 };
+use kata_types::config::hypervisor::Hypervisor as HypervisorConfig;
 
 use super::{build_dragonball_network_config, DragonballInner};
 use crate::VhostUserConfig;
@@ -36,6 +37,26 @@ pub(crate) fn drive_index_to_id(index: u64) -> String {
     format!("drive_{}", index)
 }
 
+/// DAX cache window size in bytes for a virtio-fs device. A per-device
+/// `dax_cache_size` takes precedence over the hypervisor-wide cache size,
+/// with 0 disabling DAX for this device.
+pub(crate) fn share_fs_cache_size(hconfig: &HypervisorConfig, config: &ShareFsConfig) -> u64 {
+    match config.dax_cache_size {
+        Some(size) => size,
+        None => (hconfig.shared_fs.virtio_fs_cache_size as u64).saturating_mul(MB_TO_B as u64),
+    }
+}
+
+/// Map a share-fs mount operation to the backend fs manipulation ops keyword.
+pub(crate) fn share_fs_mount_ops(op: ShareFsMountOperation) -> &'static str {
+    match op {
+        ShareFsMountOperation::Mount => "mount",
+        ShareFsMountOperation::Umount => "umount",
+        ShareFsMountOperation::Update => "update",
+        ShareFsMountOperation::Remount => "remount",
+    }
+}
+
 impl DragonballInner {
     pub(crate) async fn add_device(&mut self, device: DeviceType) -> Result<()> {
         if self.state == VmmState::NotReady {
@@ -344,8 +365,7 @@ impl DragonballInner {
             } else {
                 DEFAULT_VIRTIO_FS_QUEUE_SIZE as u16
             },
-            cache_size: (self.config.shared_fs.virtio_fs_cache_size as u64)
-                .saturating_mul(MB_TO_B as u64),
+            cache_size: share_fs_cache_size(&self.config, config),
             xattr: true,
             ..Default::default()
         };
@@ -381,11 +401,7 @@ impl DragonballInner {
     }
 
     fn add_share_fs_mount(&mut self, config: &ShareFsMountConfig) -> Result<()> {
-        let ops = match config.op {
-            ShareFsMountOperation::Mount => "mount",
-            ShareFsMountOperation::Umount => "umount",
-            ShareFsMountOperation::Update => "update",
-        };
+        let ops = share_fs_mount_ops(config.op);
 
         let fstype = match config.fstype {
             ShareFsMountType::PASSTHROUGH => "passthroughfs",
@@ -419,7 +435,10 @@ impl DragonballInner {
 mod tests {
     use dragonball::api::v1::FsDeviceConfigInfo;
 
+    use super::{share_fs_cache_size, share_fs_mount_ops, MB_TO_B};
     use crate::dragonball::DragonballInner;
+    use crate::{ShareFsConfig, ShareFsMountOperation};
+    use kata_types::config::hypervisor::Hypervisor as HypervisorConfig;
 
     #[test]
     fn test_parse_inline_virtiofs_args() {
@@ -450,4 +469,39 @@ mod tests {
         assert!(fs_cfg.drop_sys_resource);
         assert!(fs_cfg.thread_pool_size == 128);
     }
+
+    #[test]
+    fn test_share_fs_cache_size() {
+        let mut hconfig = HypervisorConfig::default();
+        hconfig.shared_fs.virtio_fs_cache_size = 1024;
+
+        // no per-device size configured: fall back to the hypervisor-wide one
+        let mut config = ShareFsConfig::default();
+        assert_eq!(
+            share_fs_cache_size(&hconfig, &config),
+            1024 * MB_TO_B as u64
+        );
+
+        // per-device DAX window size wins over the hypervisor-wide one
+        config.dax_cache_size = Some(512 * MB_TO_B as u64);
+        assert_eq!(
+            share_fs_cache_size(&hconfig, &config),
+            512 * MB_TO_B as u64
+        );
+
+        // explicit 0 disables DAX for this device
+        config.dax_cache_size = Some(0);
+        assert_eq!(share_fs_cache_size(&hconfig, &config), 0);
+    }
+
+    #[test]
+    fn test_share_fs_mount_ops() {
+        assert_eq!(share_fs_mount_ops(ShareFsMountOperation::Mount), "mount");
+        assert_eq!(share_fs_mount_ops(ShareFsMountOperation::Umount), "umount");
+        assert_eq!(share_fs_mount_ops(ShareFsMountOperation::Update), "update");
+        assert_eq!(
+            share_fs_mount_ops(ShareFsMountOperation::Remount),
+            "remount"
+        );
+    }
 }
@@ -56,6 +56,12 @@ impl Dragonball {
         inner.set_hypervisor_config(config)
     }
 
+    /// Whether the vmm instance has started booting.
+    pub async fn is_booted(&self) -> bool {
+        let inner = self.inner.read().await;
+        inner.is_booted()
+    }
+
     pub async fn set_passfd_listener_port(&mut self, port: u32) {
         let mut inner = self.inner.write().await;
         inner.set_passfd_listener_port(port)
@@ -240,12 +246,8 @@ pub(crate) fn build_dragonball_network_config(
     let virtio_config = DragonballVirtioConfig {
         iface_id: nconfig.virt_iface_name.clone(),
         host_dev_name: nconfig.host_dev_name.clone(),
-        // TODO(justxuewei): rx_rate_limiter is not supported, see:
-        // https://github.com/kata-containers/kata-containers/issues/8327.
-        rx_rate_limiter: None,
-        // TODO(justxuewei): tx_rate_limiter is not supported, see:
-        // https://github.com/kata-containers/kata-containers/issues/8327.
-        tx_rate_limiter: None,
+        rx_rate_limiter: nconfig.rx_rate_limiter.clone(),
+        tx_rate_limiter: nconfig.tx_rate_limiter.clone(),
         allow_duplicate_mac: nconfig.allow_duplicate_mac,
     };
 
@@ -267,3 +269,77 @@ pub(crate) fn build_dragonball_network_config(
         use_generic_irq: nconfig.use_generic_irq,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use dragonball::config_manager::{RateLimiterConfigInfo, TokenBucketConfigInfo};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_set_hypervisor_config_ignored_after_boot() {
+        use crate::VmmState;
+
+        let mut hypervisor = Dragonball::new();
+        assert!(!hypervisor.is_booted().await);
+
+        let mut config = HypervisorConfig::default();
+        config.boot_info.kernel = "/boot/vmlinuz-a".to_string();
+        hypervisor.set_hypervisor_config(config.clone()).await;
+        assert_eq!(
+            hypervisor.inner.read().await.hypervisor_config().boot_info.kernel,
+            "/boot/vmlinuz-a"
+        );
+
+        // pretend the guest has booted
+        hypervisor.inner.write().await.state = VmmState::VmRunning;
+        assert!(hypervisor.is_booted().await);
+
+        // a config change after boot is ignored
+        let mut late_config = config.clone();
+        late_config.boot_info.kernel = "/boot/vmlinuz-b".to_string();
+        hypervisor.set_hypervisor_config(late_config).await;
+        assert_eq!(
+            hypervisor.inner.read().await.hypervisor_config().boot_info.kernel,
+            "/boot/vmlinuz-a"
+        );
+    }
+
+    #[test]
+    fn test_build_network_config_with_rate_limiters() {
+        let rate_limiter = RateLimiterConfigInfo {
+            bandwidth: TokenBucketConfigInfo {
+                size: 1000,
+                one_time_burst: 100,
+                refill_time: 10,
+            },
+            ops: TokenBucketConfigInfo {
+                size: 500,
+                one_time_burst: 0,
+                refill_time: 20,
+            },
+        };
+
+        let mut hconfig = HypervisorConfig::default();
+        hconfig.network_info.disable_vhost_net = true;
+
+        let nconfig = NetworkConfig {
+            host_dev_name: "tap0".to_string(),
+            virt_iface_name: "eth0".to_string(),
+            queue_size: 256,
+            queue_num: 2,
+            rx_rate_limiter: Some(rate_limiter.clone()),
+            tx_rate_limiter: Some(rate_limiter.clone()),
+            ..Default::default()
+        };
+
+        let net_cfg = build_dragonball_network_config(&hconfig, &nconfig);
+        match net_cfg.backend {
+            DragonballBackend::Virtio(virtio_config) => {
+                assert_eq!(virtio_config.rx_rate_limiter, Some(rate_limiter.clone()));
+                assert_eq!(virtio_config.tx_rate_limiter, Some(rate_limiter));
+            }
+            _ => panic!("unexpected network backend"),
+        }
+    }
+}
@@ -8,27 +8,65 @@ pub mod sandbox_persist;
 use anyhow::{anyhow, Context, Ok, Result};
 use kata_types::config::KATA_PATH;
 use serde::de;
-use std::{fs::File, io::BufReader};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    os::unix::io::AsRawFd,
+    path::Path,
+};
 
 pub const PERSIST_FILE: &str = "state.json";
+
+/// Version of the persisted state format written by this runtime.
+///
+/// Version 1 is the historical format where the state was written as a bare
+/// JSON value without any envelope.
+pub const PERSIST_VERSION: u32 = 2;
+
 use kata_sys_util::validate::verify_id;
 use safe_path::scoped_join;
 
 pub fn to_disk<T: serde::Serialize>(value: &T, sid: &str) -> Result<()> {
     verify_id(sid).context("failed to verify sid")?;
-    let mut path = scoped_join(KATA_PATH, sid)?;
+    let path = scoped_join(KATA_PATH, sid)?;
     if path.exists() {
-        path.push(PERSIST_FILE);
-        let f = File::create(path)
-            .context("failed to create the file")
-            .context("failed to join the path")?;
+        // writers of the same sandbox must not interleave, so hold an
+        // exclusive lock until the new document has been moved into place
+        let _lock = lock_sandbox_state(&path)?;
+
+        let tmp_path = path.join(format!("{}.tmp", PERSIST_FILE));
+        let f = File::create(&tmp_path).context("failed to create the file")?;
         let j = serde_json::to_value(value).context("failed to convert to the json value")?;
+        let j = serde_json::json!({
+            "version": PERSIST_VERSION,
+            "state": j,
+        });
         serde_json::to_writer_pretty(f, &j)?;
+        // move the complete document into place so that readers never
+        // observe a partially written state file
+        fs::rename(&tmp_path, path.join(PERSIST_FILE)).context("failed to rename the file")?;
         return Ok(());
     }
     Err(anyhow!("invalid sid {}", sid))
 }
 
+/// Serialize writers of the same sandbox state by taking an exclusive flock
+/// on a sidecar lock file; the lock is released when the returned file is
+/// dropped.
+fn lock_sandbox_state(dir: &Path) -> Result<File> {
+    let lock_path = dir.join(format!("{}.lock", PERSIST_FILE));
+    let lock_file = File::create(&lock_path).context("failed to create the lock file")?;
+    let ret = unsafe { libc::flock(lock_file.as_raw_fd(), libc::LOCK_EX) };
+    if ret != 0 {
+        return Err(anyhow!(
+            "failed to lock {}: {}",
+            lock_path.display(),
+            std::io::Error::last_os_error()
+        ));
+    }
+    Ok(lock_file)
+}
+
 pub fn from_disk<T>(sid: &str) -> Result<T>
 where
     T: de::DeserializeOwned,
@@ -39,11 +77,41 @@ where
         path.push(PERSIST_FILE);
         let file = File::open(path).context("failed to open the file")?;
         let reader = BufReader::new(file);
-        return serde_json::from_reader(reader).map_err(|e| anyhow!(e.to_string()));
+        let value: serde_json::Value =
+            serde_json::from_reader(reader).map_err(|e| anyhow!(e.to_string()))?;
+        let state = migrate_state(value)?;
+        return serde_json::from_value(state).map_err(|e| anyhow!(e.to_string()));
     }
     Err(anyhow!("invalid sid {}", sid))
 }
 
+/// Upgrade a persisted JSON document to the current version and return the
+/// inner state value.
+///
+/// Version 1 documents are bare state values written without an envelope, so
+/// a document carrying no version field is taken as version 1. Documents
+/// newer than [`PERSIST_VERSION`] are rejected since they may contain fields
+/// this runtime does not understand.
+fn migrate_state(value: serde_json::Value) -> Result<serde_json::Value> {
+    let version = match value.get("version").and_then(|v| v.as_u64()) {
+        Some(version) => version as u32,
+        // version 1 wrote the state as a bare value without an envelope,
+        // nothing to unwrap
+        None => return Ok(value),
+    };
+    match version {
+        PERSIST_VERSION => value
+            .get("state")
+            .cloned()
+            .ok_or_else(|| anyhow!("no state found in the persisted document")),
+        _ => Err(anyhow!(
+            "unsupported persist version {}, current version is {}",
+            version,
+            PERSIST_VERSION
+        )),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{from_disk, to_disk, KATA_PATH};
@@ -82,4 +150,81 @@ mod tests {
             assert!(fs::remove_dir_all(&sandbox_dir).is_ok());
         }
     }
+
+    #[test]
+    fn test_persist_version_migration() {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct Kata {
+            name: String,
+            key: u8,
+        }
+
+        let sid = "aadedf";
+        let sandbox_dir = [KATA_PATH, sid].join("/");
+        if DirBuilder::new()
+            .recursive(true)
+            .create(&sandbox_dir)
+            .is_ok()
+        {
+            let state_file = [sandbox_dir.as_str(), crate::PERSIST_FILE].join("/");
+
+            // a version 1 document is a bare state value without an envelope
+            assert!(fs::write(&state_file, r#"{"name": "kata", "key": 1}"#).is_ok());
+            if let Ok(result) = from_disk::<Kata>(sid) {
+                assert_eq!(result.name, "kata");
+                assert_eq!(result.key, 1);
+            }
+
+            // documents newer than the current version are rejected
+            let future = format!(
+                r#"{{"version": {}, "state": {{"name": "kata", "key": 1}}}}"#,
+                crate::PERSIST_VERSION + 1
+            );
+            assert!(fs::write(&state_file, future).is_ok());
+            assert!(from_disk::<Kata>(sid).is_err());
+
+            assert!(fs::remove_dir_all(&sandbox_dir).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_concurrent_to_disk() {
+        #[derive(Serialize, Deserialize, Debug)]
+        struct Kata {
+            name: String,
+            key: u8,
+        }
+
+        let sid = "aadeea";
+        let sandbox_dir = [KATA_PATH, sid].join("/");
+        if DirBuilder::new()
+            .recursive(true)
+            .create(&sandbox_dir)
+            .is_ok()
+        {
+            let mut handlers = vec![];
+            for i in 0..8u8 {
+                handlers.push(std::thread::spawn(move || {
+                    let data = Kata {
+                        name: format!("kata-{}", i),
+                        key: i,
+                    };
+                    assert!(to_disk(&data, "aadeea").is_ok());
+                }));
+            }
+            for handler in handlers {
+                assert!(handler.join().is_ok());
+            }
+
+            // whichever writer won, the final document must be a complete,
+            // consistent one
+            let result = from_disk::<Kata>(sid);
+            assert!(result.is_ok());
+            if let Ok(result) = result {
+                assert_eq!(result.name, format!("kata-{}", result.key));
+            }
+
+            assert!(fs::remove_dir_all(&sandbox_dir).is_ok());
+        }
+    }
 }
@@ -30,6 +30,8 @@ pub struct VlanEndpointState {
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub struct VethEndpointState {
     pub if_name: String,
+    #[serde(default)]
+    pub tap_if_name: String,
     pub network_qos: bool,
 }
 
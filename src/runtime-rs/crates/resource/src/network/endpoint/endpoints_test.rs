@@ -15,8 +15,13 @@ mod tests {
     use tests_utils::load_test_config;
     use tokio::sync::RwLock;
 
+    use persist::sandbox_persist::Persist;
+
     use crate::network::{
-        endpoint::{IPVlanEndpoint, MacVlanEndpoint, VlanEndpoint},
+        endpoint::{
+            veth_endpoint::VethEndpointRestoreArgs, IPVlanEndpoint, MacVlanEndpoint, VethEndpoint,
+            VlanEndpoint,
+        },
         network_model::{
             self,
             tc_filter_model::{fetch_index, TcFilterModel},
@@ -116,6 +121,8 @@ mod tests {
                                 },
                                 model: Arc::new(TcFilterModel::new().unwrap()), // impossible to panic
                                 network_qos: false,
+                                routes: vec![],
+                                index_cache: Default::default(),
                             },
                         };
 
@@ -244,6 +251,8 @@ mod tests {
                                 model: network_model::new(model_str)
                                     .expect("failed to create new network model"),
                                 network_qos: false,
+                                routes: vec![],
+                                index_cache: Default::default(),
                             },
                         };
 
@@ -297,6 +306,91 @@ mod tests {
         }
     }
 
+    // this unit test tests that a VethEndpoint restored from its saved state
+    // matches the original one, with the interfaces re-resolved by name over
+    // a fresh rtnetlink connection
+    #[actix_rt::test]
+    async fn test_veth_endpoint_restore() {
+        let idx = 8195;
+        let manual_virt_iface_name = format!("eth{}", idx);
+        let tap_iface_name = format!("tap{}_kata", idx); // create by NetworkPair::new()
+        let dm = get_device_manager().await;
+        assert!(dm.is_ok());
+        let d = dm.unwrap();
+
+        if let Ok((conn, handle, _)) =
+            rtnetlink::new_connection().context("failed to create netlink connection")
+        {
+            let thread_handler = tokio::spawn(conn);
+            defer!({
+                thread_handler.abort();
+            });
+
+            // since VethEndpoint::new() needs an EXISTING virt_iface (which is
+            // created by containerd normally), we have to manually create one.
+            if let Ok(()) = handle
+                .link()
+                .add()
+                .veth("foo-restore".to_string(), manual_virt_iface_name.clone())
+                .execute()
+                .await
+                .context("failed to create manual veth pair")
+            {
+                if let Ok(endpoint) =
+                    VethEndpoint::new(&d, &handle, "", idx, TC_FILTER_NET_MODEL_STR, 5)
+                        .await
+                        .context("failed to create new veth endpoint")
+                {
+                    let state = Persist::save(&endpoint)
+                        .await
+                        .expect("failed to save veth endpoint state");
+
+                    let restored =
+                        VethEndpoint::restore(VethEndpointRestoreArgs { d: d.clone() }, state)
+                            .await
+                            .expect("failed to restore veth endpoint");
+
+                    // the two ends were re-resolved by name, so names, MAC
+                    // addresses and index must match the original endpoint
+                    assert_eq!(
+                        restored.net_pair.virt_iface.name,
+                        endpoint.net_pair.virt_iface.name
+                    );
+                    assert_eq!(
+                        restored.net_pair.virt_iface.hard_addr,
+                        endpoint.net_pair.virt_iface.hard_addr
+                    );
+                    assert_eq!(
+                        restored.net_pair.tap.tap_iface.name,
+                        endpoint.net_pair.tap.tap_iface.name
+                    );
+                    assert_eq!(
+                        restored.net_pair.tap.tap_iface.hard_addr,
+                        endpoint.net_pair.tap.tap_iface.hard_addr
+                    );
+                    assert_eq!(
+                        fetch_index(&handle, restored.net_pair.virt_iface.name.as_str())
+                            .await
+                            .expect("failed to re-resolve the virt iface index"),
+                        fetch_index(&handle, endpoint.net_pair.virt_iface.name.as_str())
+                            .await
+                            .expect("failed to fetch the virt iface index"),
+                    );
+                    // the model is re-created by name instead of being persisted
+                    match restored.net_pair.model.model_type() {
+                        NetworkModelType::TcFilter => {} // ok
+                        _ => unreachable!(),
+                    }
+                    assert_eq!(restored.net_pair.network_qos, endpoint.net_pair.network_qos);
+                }
+                assert!(delete_link(&handle, manual_virt_iface_name.as_str())
+                    .await
+                    .is_ok());
+                assert!(delete_link(&handle, tap_iface_name.as_str()).await.is_ok());
+            }
+        }
+    }
+
     // this unit test tests the integrity of IPVlanEndpoint::new()
     #[actix_rt::test]
     async fn test_ipvlan_construction() {
@@ -348,6 +442,8 @@ mod tests {
                             },
                             model: Arc::new(TcFilterModel::new().unwrap()), // impossible to panic
                             network_qos: false,
+                            routes: vec![],
+                            index_cache: Default::default(),
                         },
                     };
 
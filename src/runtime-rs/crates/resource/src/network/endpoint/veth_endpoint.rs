@@ -7,17 +7,19 @@
 use std::io::{self, Error};
 use std::sync::Arc;
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use hypervisor::device::device_manager::{do_handle_device, DeviceManager};
 use hypervisor::device::driver::NetworkConfig;
 use hypervisor::device::{DeviceConfig, DeviceType};
 use hypervisor::{Hypervisor, NetworkDevice};
+use persist::sandbox_persist::Persist;
+use scopeguard::defer;
 use tokio::sync::RwLock;
 
 use super::endpoint_persist::{EndpointState, VethEndpointState};
 use super::Endpoint;
-use crate::network::{utils, NetworkPair};
+use crate::network::{network_model::TC_FILTER_NET_MODEL_STR, utils, NetworkPair};
 
 #[derive(Debug)]
 pub struct VethEndpoint {
@@ -87,10 +89,19 @@ impl Endpoint for VethEndpoint {
     }
 
     async fn detach(&self, h: &dyn Hypervisor) -> Result<()> {
+        let (connection, handle, _) =
+            rtnetlink::new_connection().context("new rtnetlink connection")?;
+        let thread_handler = tokio::spawn(connection);
+        defer!({
+            thread_handler.abort();
+        });
+
+        // remove the model's steering rules and destroy the tap, in that
+        // order
         self.net_pair
-            .del_network_model()
+            .teardown(&handle)
             .await
-            .context("del network model failed.")?;
+            .context("tear down network pair failed.")?;
 
         let config = self.get_network_config().context("get network config")?;
         h.remove_device(DeviceType::Network(NetworkDevice {
@@ -107,9 +118,62 @@ impl Endpoint for VethEndpoint {
         Some(EndpointState {
             veth_endpoint: Some(VethEndpointState {
                 if_name: self.net_pair.virt_iface.name.clone(),
+                tap_if_name: self.net_pair.tap.tap_iface.name.clone(),
                 network_qos: self.net_pair.network_qos,
             }),
             ..Default::default()
         })
     }
 }
+
+/// Arguments needed to restore a [`VethEndpoint`].
+///
+/// The rtnetlink handle the endpoint was first created with cannot be
+/// persisted, so restore opens a fresh connection by itself and only the
+/// device manager has to be handed back in.
+pub struct VethEndpointRestoreArgs {
+    pub d: Arc<RwLock<DeviceManager>>,
+}
+
+#[async_trait]
+impl Persist for VethEndpoint {
+    type State = EndpointState;
+    type ConstructorArgs = VethEndpointRestoreArgs;
+
+    /// Save a state of the endpoint.
+    async fn save(&self) -> Result<Self::State> {
+        Endpoint::save(self)
+            .await
+            .ok_or_else(|| anyhow!("failed to save veth endpoint state"))
+    }
+
+    /// Restore the endpoint from a saved state, re-resolving the two ends of
+    /// the network pair by name over a freshly opened rtnetlink connection.
+    async fn restore(restore_args: Self::ConstructorArgs, state: Self::State) -> Result<Self> {
+        let endpoint_state = state
+            .veth_endpoint
+            .ok_or_else(|| anyhow!("no veth endpoint state found"))?;
+
+        let (connection, handle, _) =
+            rtnetlink::new_connection().context("new rtnetlink connection")?;
+        let thread_handler = tokio::spawn(connection);
+        defer!({
+            thread_handler.abort();
+        });
+
+        let net_pair = NetworkPair::restore(
+            &handle,
+            &endpoint_state.if_name,
+            &endpoint_state.tap_if_name,
+            TC_FILTER_NET_MODEL_STR,
+            endpoint_state.network_qos,
+        )
+        .await
+        .context("restore network pair")?;
+
+        Ok(VethEndpoint {
+            net_pair,
+            d: restore_args.d,
+        })
+    }
+}
@@ -21,7 +21,7 @@ pub use network_with_netns::NetworkWithNetNsConfig;
 use network_with_netns::NetworkWithNetns;
 mod network_pair;
 use network_pair::NetworkPair;
-mod utils;
+pub mod utils;
 pub use kata_sys_util::netns::{generate_netns_name, NetnsGuard};
 use tokio::sync::RwLock;
 
@@ -84,15 +84,7 @@ pub async fn handle_addresses(
             continue;
         }
 
-        addresses.push(IPAddress {
-            family: if a.addr.is_ipv4() {
-                IPFamily::V4
-            } else {
-                IPFamily::V6
-            },
-            address: a.addr.to_string(),
-            mask: a.perfix_len.to_string(),
-        });
+        addresses.push(IPAddress::from(&a));
     }
     Ok(addresses)
 }
@@ -5,8 +5,10 @@
 //
 
 pub mod none_model;
+pub mod route_model;
 pub mod tc_filter_model;
 pub mod test_network_model;
+use std::fmt;
 use std::sync::Arc;
 
 use anyhow::{Context, Result};
@@ -14,17 +16,42 @@ use async_trait::async_trait;
 
 use super::NetworkPair;
 
+pub(crate) const NONE_NET_MODEL_STR: &str = "none";
+pub(crate) const ROUTE_NET_MODEL_STR: &str = "route";
 pub(crate) const TC_FILTER_NET_MODEL_STR: &str = "tcfilter";
 
 pub enum NetworkModelType {
     NoneModel,
+    Route,
     TcFilter,
 }
 
+impl NetworkModelType {
+    /// Return the name of the model, the inverse of the mapping done by
+    /// [`new`], so that the model choice can be round-tripped through its
+    /// string form.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkModelType::NoneModel => NONE_NET_MODEL_STR,
+            NetworkModelType::Route => ROUTE_NET_MODEL_STR,
+            NetworkModelType::TcFilter => TC_FILTER_NET_MODEL_STR,
+        }
+    }
+}
+
+impl fmt::Display for NetworkModelType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[async_trait]
 pub trait NetworkModel: std::fmt::Debug + Send + Sync {
     fn model_type(&self) -> NetworkModelType;
     async fn add(&self, net_pair: &NetworkPair) -> Result<()>;
+    /// Remove the steering rules installed by add(). This must run while
+    /// both ends of the pair still exist, i.e. before the tap is
+    /// destroyed; NetworkPair::teardown() enforces that ordering.
     async fn del(&self, net_pair: &NetworkPair) -> Result<()>;
 }
 
@@ -33,6 +60,10 @@ pub fn new(model: &str) -> Result<Arc<dyn NetworkModel>> {
         TC_FILTER_NET_MODEL_STR => Ok(Arc::new(
             tc_filter_model::TcFilterModel::new().context("new tc filter model")?,
         )),
+        ROUTE_NET_MODEL_STR => Ok(Arc::new(
+            route_model::RouteModel::new().context("new route model")?,
+        )),
+        // NONE_NET_MODEL_STR and anything unknown fall back to the none model
         _ => Ok(Arc::new(
             none_model::NoneModel::new().context("new none model")?,
         )),
@@ -0,0 +1,155 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::net::IpAddr;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use futures::stream::TryStreamExt;
+use rtnetlink::{Handle, IpVersion};
+use scopeguard::defer;
+
+use super::{NetworkModel, NetworkModelType};
+use crate::network::{utils::address::parse_ip_cidr, NetworkPair};
+
+/// A host route tied to the virt end of a network pair.
+#[derive(Clone, Debug, Default)]
+pub struct RouteEntry {
+    /// Destination in CIDR notation.
+    pub dest: String,
+    /// Optional gateway address, empty for a directly connected route.
+    pub gateway: String,
+}
+
+#[derive(Debug)]
+pub(crate) struct RouteModel {}
+
+impl RouteModel {
+    pub fn new() -> Result<Self> {
+        Ok(Self {})
+    }
+}
+
+#[async_trait]
+impl NetworkModel for RouteModel {
+    fn model_type(&self) -> NetworkModelType {
+        NetworkModelType::Route
+    }
+
+    async fn add(&self, pair: &NetworkPair) -> Result<()> {
+        let (connection, handle, _) = rtnetlink::new_connection().context("new connection")?;
+        let thread_handler = tokio::spawn(connection);
+        defer!({
+            thread_handler.abort();
+        });
+
+        let virt_index = pair
+            .fetch_index(&handle, pair.virt_iface.name.as_str())
+            .await
+            .context("fetch virt by index")?;
+
+        for route in &pair.routes {
+            add_route(&handle, virt_index, route)
+                .await
+                .with_context(|| format!("add route {}", route.dest))?;
+        }
+
+        Ok(())
+    }
+
+    async fn del(&self, pair: &NetworkPair) -> Result<()> {
+        let (connection, handle, _) = rtnetlink::new_connection().context("new connection")?;
+        let thread_handler = tokio::spawn(connection);
+        defer!({
+            thread_handler.abort();
+        });
+
+        let virt_index = pair
+            .fetch_index(&handle, pair.virt_iface.name.as_str())
+            .await
+            .context("fetch virt by index")?;
+
+        for route in &pair.routes {
+            del_route(&handle, virt_index, route)
+                .await
+                .with_context(|| format!("del route {}", route.dest))?;
+        }
+
+        Ok(())
+    }
+}
+
+async fn add_route(handle: &Handle, index: u32, route: &RouteEntry) -> Result<()> {
+    let (dest, prefix) = parse_ip_cidr(&route.dest).context("parse dest cidr")?;
+    match dest {
+        IpAddr::V4(dest) => {
+            let mut request = handle
+                .route()
+                .add()
+                .v4()
+                .destination_prefix(dest, prefix)
+                .output_interface(index);
+            if !route.gateway.is_empty() {
+                match route.gateway.parse::<IpAddr>().context("parse gateway")? {
+                    IpAddr::V4(gateway) => request = request.gateway(gateway),
+                    IpAddr::V6(_) => {
+                        return Err(anyhow!(
+                            "IPv6 gateway {} for IPv4 route {}",
+                            route.gateway,
+                            route.dest
+                        ))
+                    }
+                }
+            }
+            request.execute().await?;
+        }
+        IpAddr::V6(dest) => {
+            let mut request = handle
+                .route()
+                .add()
+                .v6()
+                .destination_prefix(dest, prefix)
+                .output_interface(index);
+            if !route.gateway.is_empty() {
+                match route.gateway.parse::<IpAddr>().context("parse gateway")? {
+                    IpAddr::V6(gateway) => request = request.gateway(gateway),
+                    IpAddr::V4(_) => {
+                        return Err(anyhow!(
+                            "IPv4 gateway {} for IPv6 route {}",
+                            route.gateway,
+                            route.dest
+                        ))
+                    }
+                }
+            }
+            request.execute().await?;
+        }
+    }
+    Ok(())
+}
+
+async fn del_route(handle: &Handle, index: u32, route: &RouteEntry) -> Result<()> {
+    let (dest, prefix) = parse_ip_cidr(&route.dest).context("parse dest cidr")?;
+    let ip_version = if dest.is_ipv4() {
+        IpVersion::V4
+    } else {
+        IpVersion::V6
+    };
+
+    let mut route_msg_list = handle.route().get(ip_version).execute();
+    let mut matched = vec![];
+    while let Some(msg) = route_msg_list.try_next().await? {
+        if msg.output_interface() == Some(index) && msg.destination_prefix() == Some((dest, prefix))
+        {
+            matched.push(msg);
+        }
+    }
+
+    for msg in matched {
+        handle.route().del(msg).execute().await?;
+    }
+    Ok(())
+}
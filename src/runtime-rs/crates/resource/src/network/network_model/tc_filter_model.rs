@@ -6,9 +6,13 @@
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use netlink_packet_route::tc::constants::TC_H_INGRESS;
 use rtnetlink::Handle;
 use scopeguard::defer;
 
+/// Handle of the ingress qdisc installed by [`TcFilterModel::add`].
+const INGRESS_QDISC_HANDLE: u32 = 0xffff0000;
+
 use super::{NetworkModel, NetworkModelType};
 use crate::network::NetworkPair;
 
@@ -35,33 +39,27 @@ impl NetworkModel for TcFilterModel {
             thread_handler.abort();
         });
 
-        let tap_index = fetch_index(&handle, pair.tap.tap_iface.name.as_str())
+        let tap_index = pair
+            .fetch_index(&handle, pair.tap.tap_iface.name.as_str())
             .await
             .context("fetch tap by index")?;
-        let virt_index = fetch_index(&handle, pair.virt_iface.name.as_str())
+        let virt_index = pair
+            .fetch_index(&handle, pair.virt_iface.name.as_str())
             .await
             .context("fetch virt by index")?;
 
-        handle
-            .qdisc()
-            .add(tap_index as i32)
-            .ingress()
-            .execute()
+        add_ingress_qdisc(&handle, tap_index as i32)
             .await
             .context("add tap ingress")?;
 
-        handle
-            .qdisc()
-            .add(virt_index as i32)
-            .ingress()
-            .execute()
+        add_ingress_qdisc(&handle, virt_index as i32)
             .await
             .context("add virt ingress")?;
 
         handle
             .traffic_filter(tap_index as i32)
             .add()
-            .parent(0xffff0000)
+            .parent(INGRESS_QDISC_HANDLE)
             // get protocol with network byte order
             .protocol(0x0003_u16.to_be())
             .redirect(virt_index)
@@ -72,7 +70,7 @@ impl NetworkModel for TcFilterModel {
         handle
             .traffic_filter(virt_index as i32)
             .add()
-            .parent(0xffff0000)
+            .parent(INGRESS_QDISC_HANDLE)
             // get protocol with network byte order
             .protocol(0x0003_u16.to_be())
             .redirect(tap_index)
@@ -89,12 +87,50 @@ impl NetworkModel for TcFilterModel {
         defer!({
             thread_handler.abort();
         });
-        let virt_index = fetch_index(&handle, &pair.virt_iface.name).await?;
-        handle.qdisc().del(virt_index as i32).execute().await?;
+        let virt_index = pair.fetch_index(&handle, &pair.virt_iface.name).await?;
+        del_ingress_qdisc(&handle, virt_index as i32)
+            .await
+            .context("del virt ingress")?;
+
+        // the tap may already be gone when the sandbox is torn down, but if
+        // it is still around its qdisc must not leak either
+        if let Ok(tap_index) = pair.fetch_index(&handle, &pair.tap.tap_iface.name).await {
+            del_ingress_qdisc(&handle, tap_index as i32)
+                .await
+                .context("del tap ingress")?;
+        }
         Ok(())
     }
 }
 
+/// Install the ingress qdisc on the given interface, clearing a stale one
+/// left behind by a previous sandbox first so that add stays idempotent.
+async fn add_ingress_qdisc(handle: &Handle, index: i32) -> Result<()> {
+    // removing a stale qdisc also removes the filters attached to it, so a
+    // fresh add never inherits old redirect rules
+    let _ = del_ingress_qdisc(handle, index).await;
+
+    handle
+        .qdisc()
+        .add(index)
+        .ingress()
+        .execute()
+        .await
+        .context("add ingress qdisc")?;
+    Ok(())
+}
+
+/// Delete exactly the ingress qdisc installed by [`add_ingress_qdisc`]; the
+/// filters attached to it are removed along with it.
+async fn del_ingress_qdisc(handle: &Handle, index: i32) -> Result<()> {
+    let mut request = handle.qdisc().del(index);
+    let message = request.message_mut();
+    message.header.parent = TC_H_INGRESS;
+    message.header.handle = INGRESS_QDISC_HANDLE;
+    request.execute().await.context("del ingress qdisc")?;
+    Ok(())
+}
+
 pub async fn fetch_index(handle: &Handle, name: &str) -> Result<u32> {
     let link = crate::network::network_pair::get_link_by_name(handle, name)
         .await
@@ -7,11 +7,164 @@
 #[cfg(test)]
 mod tests {
     use crate::network::{
-        network_model::{tc_filter_model::fetch_index, TC_FILTER_NET_MODEL_STR},
-        network_pair::NetworkPair,
+        network_model::{
+            self, route_model::RouteEntry, tc_filter_model::fetch_index, NetworkModelType,
+            NONE_NET_MODEL_STR, ROUTE_NET_MODEL_STR, TC_FILTER_NET_MODEL_STR,
+        },
+        network_pair::{create_link, NetworkInterface, NetworkPair, TapInterface},
+        utils::address::parse_ip_cidr,
     };
+    use crate::network::utils::link::net_test_utils::delete_link;
     use anyhow::Context;
+    use futures::stream::TryStreamExt;
+    use netlink_packet_route::tc::constants::TC_H_INGRESS;
     use scopeguard::defer;
+
+    async fn has_ingress_qdisc(handle: &rtnetlink::Handle, index: i32) -> bool {
+        let mut qdiscs = handle.qdisc().get().execute();
+        while let Ok(Some(msg)) = qdiscs.try_next().await {
+            if msg.header.index == index && msg.header.parent == TC_H_INGRESS {
+                return true;
+            }
+        }
+        false
+    }
+
+    async fn has_route(handle: &rtnetlink::Handle, index: u32, cidr: &str) -> bool {
+        let (dest, prefix) = parse_ip_cidr(cidr).expect("failed to parse cidr");
+        let mut routes = handle.route().get(rtnetlink::IpVersion::V4).execute();
+        while let Ok(Some(msg)) = routes.try_next().await {
+            if msg.output_interface() == Some(index)
+                && msg.destination_prefix() == Some((dest, prefix))
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    // this unit test tests that RouteModel programs every route carried on
+    // the network pair and removes them all again on del()
+    #[actix_rt::test]
+    async fn test_route_model_add_del() {
+        let iface_name = "katartm0";
+        let cidrs = ["192.168.161.0/24", "192.168.162.0/24"];
+
+        if let Ok((connection, handle, _)) = rtnetlink::new_connection().context("new connection") {
+            let thread_handler = tokio::spawn(connection);
+            defer!({
+                thread_handler.abort();
+            });
+
+            if let Ok(link) = create_link(&handle, iface_name, 2).await {
+                let index = link.attrs().index;
+                // routes can only be programmed on an interface that is up
+                assert!(handle.link().set(index).up().execute().await.is_ok());
+
+                let pair = NetworkPair {
+                    tap: TapInterface::default(),
+                    virt_iface: NetworkInterface {
+                        name: String::from(iface_name),
+                        ..Default::default()
+                    },
+                    model: network_model::new(ROUTE_NET_MODEL_STR)
+                        .expect("failed to create route model"),
+                    network_qos: false,
+                    routes: cidrs
+                        .iter()
+                        .map(|cidr| RouteEntry {
+                            dest: cidr.to_string(),
+                            ..Default::default()
+                        })
+                        .collect(),
+                    index_cache: Default::default(),
+                };
+
+                assert!(pair.add_network_model().await.is_ok());
+                for cidr in cidrs.iter() {
+                    assert!(has_route(&handle, index, cidr).await);
+                }
+
+                assert!(pair.del_network_model().await.is_ok());
+                for cidr in cidrs.iter() {
+                    assert!(!has_route(&handle, index, cidr).await);
+                }
+
+                assert!(delete_link(&handle, iface_name).await.is_ok());
+            }
+        }
+    }
+
+    // this unit test tests that TcFilterModel::add() is idempotent and that
+    // del() removes exactly the qdiscs add() installed on both ends
+    #[actix_rt::test]
+    async fn test_tc_filter_add_del_symmetric() {
+        let idx = 7777;
+        let virt_iface_name = format!("eth{}", idx);
+        let tap_name = format!("tap{}_kata", idx);
+
+        if let Ok((connection, handle, _)) = rtnetlink::new_connection().context("new connection") {
+            let thread_handler = tokio::spawn(connection);
+            defer!({
+                thread_handler.abort();
+            });
+
+            if let Ok(()) = handle
+                .link()
+                .add()
+                .veth("tcsymfoo".to_string(), virt_iface_name.clone())
+                .execute()
+                .await
+                .context("failed to create veth pair")
+            {
+                if let Ok(net_pair) =
+                    NetworkPair::new(&handle, idx, "", TC_FILTER_NET_MODEL_STR, 2).await
+                {
+                    let tap_index = fetch_index(&handle, tap_name.as_str())
+                        .await
+                        .expect("failed to fetch the tap index")
+                        as i32;
+                    let virt_index = fetch_index(&handle, virt_iface_name.as_str())
+                        .await
+                        .expect("failed to fetch the virt index")
+                        as i32;
+
+                    // adding twice must succeed even though the first add
+                    // left an ingress qdisc behind
+                    assert!(net_pair.add_network_model().await.is_ok());
+                    assert!(net_pair.add_network_model().await.is_ok());
+                    assert!(has_ingress_qdisc(&handle, tap_index).await);
+                    assert!(has_ingress_qdisc(&handle, virt_index).await);
+
+                    // del must remove exactly what add installed, on both ends
+                    assert!(net_pair.del_network_model().await.is_ok());
+                    assert!(!has_ingress_qdisc(&handle, tap_index).await);
+                    assert!(!has_ingress_qdisc(&handle, virt_index).await);
+
+                    assert!(delete_link(&handle, virt_iface_name.as_str()).await.is_ok());
+                    assert!(delete_link(&handle, tap_name.as_str()).await.is_ok());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_model_type_string_roundtrip() {
+        // every model type must round-trip through its string form and back
+        // via network_model::new()
+        for model_type in [
+            NetworkModelType::NoneModel,
+            NetworkModelType::Route,
+            NetworkModelType::TcFilter,
+        ] {
+            let model_str = model_type.as_str();
+            let model = network_model::new(model_str).expect("failed to create network model");
+            assert_eq!(model.model_type().as_str(), model_str);
+            assert_eq!(model.model_type().to_string(), model_str);
+        }
+        assert_eq!(NetworkModelType::NoneModel.as_str(), NONE_NET_MODEL_STR);
+        assert_eq!(NetworkModelType::TcFilter.as_str(), TC_FILTER_NET_MODEL_STR);
+    }
     #[actix_rt::test]
     async fn test_tc_redirect_network() {
         if let Ok((connection, handle, _)) = rtnetlink::new_connection().context("new connection") {
@@ -4,7 +4,12 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
-use std::{convert::TryFrom, sync::Arc, usize};
+use std::{
+    collections::HashMap,
+    convert::TryFrom,
+    sync::{Arc, Mutex},
+    usize,
+};
 
 use anyhow::{anyhow, Context, Result};
 use futures::stream::TryStreamExt;
@@ -38,6 +43,13 @@ pub struct NetworkPair {
     pub virt_iface: NetworkInterface,
     pub model: Arc<dyn network_model::NetworkModel>,
     pub network_qos: bool,
+    /// Host routes tied to the virt end of the pair, managed by the route
+    /// network model.
+    pub routes: Vec<network_model::route_model::RouteEntry>,
+    /// Cache of interface indices resolved over rtnetlink, so repeated
+    /// lookups of the same name during one endpoint setup do not go back
+    /// to the kernel.
+    pub(crate) index_cache: Mutex<HashMap<String, u32>>,
 }
 
 impl NetworkPair {
@@ -56,6 +68,18 @@ impl NetworkPair {
             .await
             .context("create link")?;
 
+        // Offloads the backing driver cannot handle must be switched off
+        // before any traffic flows, otherwise the guest receives frames
+        // with broken checksums.
+        let disabled = link::disable_unsupported_offloads(&tap_iface_name)
+            .context("disable unsupported offloads")?;
+        if !disabled.is_empty() {
+            info!(
+                sl!(),
+                "disabled offloads {:?} on {}", disabled, tap_iface_name
+            );
+        }
+
         let virt_link = get_link_by_name(handle, virt_iface_name.clone().as_str())
             .await
             .context("get link by name")?;
@@ -86,11 +110,10 @@ impl NetworkPair {
         let virt_hard_addr =
             utils::get_mac_addr(&tap_link.attrs().hardware_addr).context("get mac addr")?;
 
-        handle
-            .link()
-            .set(tap_link.attrs().index)
-            .mtu(virt_link.attrs().mtu)
-            .execute()
+        // The tap must carry the same MTU as the veth it is paired with,
+        // otherwise jumbo frames configured by the network plugin would be
+        // truncated on their way into the guest.
+        link::set_mtu(handle, &tap_iface_name, virt_link.attrs().mtu)
             .await
             .context("set link mtu")?;
 
@@ -119,6 +142,8 @@ impl NetworkPair {
             },
             model,
             network_qos: false,
+            routes: vec![],
+            index_cache: Default::default(),
         };
 
         if !name.is_empty() {
@@ -128,6 +153,96 @@ impl NetworkPair {
         Ok(net_pair)
     }
 
+    /// Rebuild the in-memory representation of a network pair whose two ends
+    /// already exist on the host, typically after the runtime restarts.
+    ///
+    /// Nothing is created here: the interfaces are only looked up again by
+    /// name over the given handle to re-resolve their index and attributes,
+    /// and the network model is re-created from its name since the model
+    /// itself holds no state.
+    pub(crate) async fn restore(
+        handle: &rtnetlink::Handle,
+        virt_iface_name: &str,
+        tap_iface_name: &str,
+        model: &str,
+        network_qos: bool,
+    ) -> Result<Self> {
+        let unique_id = kata_sys_util::rand::UUID::new();
+        let model = network_model::new(model).context("new network model")?;
+
+        let tap_link = get_link_by_name(handle, tap_iface_name)
+            .await
+            .context("get tap link by name")?;
+        let virt_link = get_link_by_name(handle, virt_iface_name)
+            .await
+            .context("get virt link by name")?;
+
+        let mut virt_addr_msg_list = handle
+            .address()
+            .get()
+            .set_link_index_filter(virt_link.attrs().index)
+            .execute();
+
+        let mut virt_address = vec![];
+        while let Some(addr_msg) = virt_addr_msg_list.try_next().await? {
+            let addr = Address::try_from(addr_msg).context("get address from msg")?;
+            virt_address.push(addr);
+        }
+
+        // The MAC addresses were swapped between the two ends when the pair
+        // was first created, recover them the same way here.
+        let tap_hard_addr =
+            utils::get_mac_addr(&virt_link.attrs().hardware_addr).context("get mac addr")?;
+        let virt_hard_addr =
+            utils::get_mac_addr(&tap_link.attrs().hardware_addr).context("get mac addr")?;
+
+        Ok(NetworkPair {
+            tap: TapInterface {
+                id: String::from(&unique_id),
+                name: tap_iface_name.replacen("tap", "br", 1),
+                tap_iface: NetworkInterface {
+                    name: String::from(tap_iface_name),
+                    hard_addr: tap_hard_addr,
+                    ..Default::default()
+                },
+            },
+            virt_iface: NetworkInterface {
+                name: String::from(virt_iface_name),
+                hard_addr: virt_hard_addr,
+                addrs: virt_address,
+            },
+            model,
+            network_qos,
+            routes: vec![],
+            index_cache: Default::default(),
+        })
+    }
+
+    /// Resolve the index of the named interface, consulting the per-pair
+    /// cache before issuing an rtnetlink lookup.
+    pub(crate) async fn fetch_index(&self, handle: &rtnetlink::Handle, name: &str) -> Result<u32> {
+        if let Some(index) = self.index_cache.lock().unwrap().get(name).copied() {
+            return Ok(index);
+        }
+
+        let link = get_link_by_name(handle, name)
+            .await
+            .context("get link by name")?;
+        let index = link.attrs().index;
+        self.index_cache
+            .lock()
+            .unwrap()
+            .insert(String::from(name), index);
+
+        Ok(index)
+    }
+
+    /// Drop a cached index, to be called when the link is deleted or
+    /// recreated so that the next lookup goes back to the kernel.
+    pub(crate) fn invalidate_index(&self, name: &str) {
+        self.index_cache.lock().unwrap().remove(name);
+    }
+
     pub(crate) async fn add_network_model(&self) -> Result<()> {
         let model = self.model.clone();
         model.add(self).await.context("add")?;
@@ -137,6 +252,32 @@ impl NetworkPair {
     pub(crate) async fn del_network_model(&self) -> Result<()> {
         let model = self.model.clone();
         model.del(self).await.context("del")?;
+        // the caller usually deletes the pair's links right after tearing
+        // down the model, so the cached indices are about to go stale
+        self.invalidate_index(&self.tap.tap_iface.name);
+        self.invalidate_index(&self.virt_iface.name);
+        Ok(())
+    }
+
+    /// Tear the pair down in one step: the steering rules installed by the
+    /// network model MUST be removed before the tap itself is destroyed,
+    /// otherwise filters referencing the dead ifindex are left behind in
+    /// the kernel. Callers that cannot use this helper have to preserve
+    /// the same ordering by hand.
+    pub(crate) async fn teardown(&self, handle: &rtnetlink::Handle) -> Result<()> {
+        self.del_network_model()
+            .await
+            .context("del network model")?;
+
+        let link = get_link_by_name(handle, self.tap.tap_iface.name.as_str())
+            .await
+            .context("get tap link")?;
+        handle
+            .link()
+            .del(link.attrs().index)
+            .execute()
+            .await
+            .context("delete tap link")?;
         Ok(())
     }
 }
@@ -184,7 +325,7 @@ mod tests {
     use scopeguard::defer;
 
     use super::*;
-    use crate::network::network_model::TC_FILTER_NET_MODEL_STR;
+    use crate::network::network_model::{NONE_NET_MODEL_STR, TC_FILTER_NET_MODEL_STR};
     use test_utils::skip_if_not_root;
     use utils::link::net_test_utils::delete_link;
 
@@ -219,6 +360,132 @@ mod tests {
         }
     }
 
+    #[actix_rt::test]
+    async fn test_fetch_index_cache() {
+        let iface_name = "kata_test_cache";
+
+        skip_if_not_root!();
+
+        if let Ok((conn, handle, _)) =
+            rtnetlink::new_connection().context("failed to create netlink connection")
+        {
+            let thread_handler = tokio::spawn(conn);
+            defer!({
+                thread_handler.abort();
+            });
+
+            assert!(create_link(&handle, iface_name, 2).await.is_ok());
+
+            let pair = NetworkPair {
+                tap: TapInterface::default(),
+                virt_iface: NetworkInterface {
+                    name: String::from(iface_name),
+                    ..Default::default()
+                },
+                model: network_model::new(NONE_NET_MODEL_STR)
+                    .expect("failed to create network model"),
+                network_qos: false,
+                routes: vec![],
+                index_cache: Default::default(),
+            };
+
+            let index = pair
+                .fetch_index(&handle, iface_name)
+                .await
+                .expect("failed to fetch the interface index");
+
+            // while the link exists, repeated lookups are served from the
+            // cache and keep resolving to the same index
+            assert_eq!(pair.fetch_index(&handle, iface_name).await.unwrap(), index);
+
+            // tearing down the model invalidates the cached indices, so once
+            // the link is gone the next lookup goes back to the kernel and
+            // notices instead of serving a stale index
+            assert!(delete_link(&handle, iface_name).await.is_ok());
+            pair.del_network_model()
+                .await
+                .expect("failed to del network model");
+            assert!(pair.fetch_index(&handle, iface_name).await.is_err());
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_teardown_removes_model_before_tap() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        use async_trait::async_trait;
+
+        use crate::network::network_model::{NetworkModel, NetworkModelType};
+
+        let tap_name = "kata_test_td";
+
+        // mock model recording whether the tap still existed when the
+        // steering rules were removed
+        #[derive(Debug)]
+        struct MockModel {
+            tap_name: String,
+            tap_alive_at_del: Arc<AtomicBool>,
+        }
+
+        #[async_trait]
+        impl NetworkModel for MockModel {
+            fn model_type(&self) -> NetworkModelType {
+                NetworkModelType::NoneModel
+            }
+            async fn add(&self, _net_pair: &NetworkPair) -> Result<()> {
+                Ok(())
+            }
+            async fn del(&self, _net_pair: &NetworkPair) -> Result<()> {
+                let alive =
+                    std::path::Path::new(&format!("/sys/class/net/{}", self.tap_name)).exists();
+                self.tap_alive_at_del.store(alive, Ordering::SeqCst);
+                Ok(())
+            }
+        }
+
+        skip_if_not_root!();
+
+        if let Ok((conn, handle, _)) =
+            rtnetlink::new_connection().context("failed to create netlink connection")
+        {
+            let thread_handler = tokio::spawn(conn);
+            defer!({
+                thread_handler.abort();
+            });
+
+            assert!(create_link(&handle, tap_name, 2).await.is_ok());
+
+            let tap_alive_at_del = Arc::new(AtomicBool::new(false));
+            let pair = NetworkPair {
+                tap: TapInterface {
+                    // production-shaped naming: `name` is only a label, the
+                    // host link is named after `tap_iface`
+                    name: String::from("br_kata_test_td"),
+                    tap_iface: NetworkInterface {
+                        name: String::from(tap_name),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                virt_iface: NetworkInterface::default(),
+                model: Arc::new(MockModel {
+                    tap_name: String::from(tap_name),
+                    tap_alive_at_del: tap_alive_at_del.clone(),
+                }),
+                network_qos: false,
+                routes: vec![],
+                index_cache: Default::default(),
+            };
+
+            assert!(pair.teardown(&handle).await.is_ok());
+
+            // the model was torn down while the tap still existed, and the
+            // tap itself is gone afterwards
+            assert!(tap_alive_at_del.load(Ordering::SeqCst));
+            assert!(get_link_by_name(&handle, tap_name).await.is_err());
+        }
+    }
+
     #[actix_rt::test]
     async fn test_network_pair() {
         let idx = 123456;
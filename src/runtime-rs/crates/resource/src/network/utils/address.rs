@@ -8,7 +8,7 @@ use std::convert::TryFrom;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use std::str::FromStr;
 
-use agent::IPFamily;
+use agent::{IPAddress, IPFamily};
 use anyhow::{anyhow, Context, Result};
 use netlink_packet_route::nlas::address::Nla;
 use netlink_packet_route::{AddressMessage, AF_INET, AF_INET6};
@@ -65,6 +65,19 @@ impl TryFrom<AddressMessage> for Address {
     }
 }
 
+/// Both IPv4 and IPv6 addresses are represented: `addr` holds the `IpAddr` of
+/// either family and `perfix_len` the prefix length, so dual-stack interfaces
+/// can program both families through the same path.
+impl From<&Address> for IPAddress {
+    fn from(addr: &Address) -> Self {
+        IPAddress {
+            family: ip_family_from_ip_addr(&addr.addr),
+            address: addr.addr.to_string(),
+            mask: addr.perfix_len.to_string(),
+        }
+    }
+}
+
 pub(crate) fn parse_ip(ip: &[u8], family: u8) -> Result<IpAddr> {
     let support_len = if family as u16 == AF_INET { 4 } else { 16 };
     if ip.len() != support_len {
@@ -146,6 +159,35 @@ mod tests {
         assert!(parse_ip(fail_ipv6.as_slice(), AF_INET6 as u8).is_err());
     }
 
+    #[test]
+    fn test_address_to_ip_address() {
+        let mut address = Address {
+            addr: IpAddr::V6(Ipv6Addr::new(0x2001, 0x4860, 0x4860, 0, 0, 0, 0, 0x8888)),
+            peer: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+            broadcast: IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)),
+            label: String::default(),
+            flags: 0,
+            scope: 0,
+            perfix_len: 64,
+            prefered_lft: 0,
+            valid_ltf: 0,
+        };
+
+        // IPv6 round-trip
+        let ip_address = IPAddress::from(&address);
+        assert_eq!(ip_address.family, IPFamily::V6);
+        assert_eq!(ip_address.address, "2001:4860:4860::8888");
+        assert_eq!(ip_address.mask, "64");
+
+        // IPv4 round-trip
+        address.addr = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1));
+        address.perfix_len = 24;
+        let ip_address = IPAddress::from(&address);
+        assert_eq!(ip_address.family, IPFamily::V4);
+        assert_eq!(ip_address.address, "192.168.0.1");
+        assert_eq!(ip_address.mask, "24");
+    }
+
     #[test]
     fn test_parse_ip_cidr() {
         let test_cases = [
@@ -11,7 +11,7 @@ use std::{
     {io, mem},
 };
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use nix::ioctl_write_ptr;
 
 use super::macros::{get_name, set_name};
@@ -77,11 +77,13 @@ const DEVICE_PATH: &str = "/dev/net/tun";
 ioctl_write_ptr!(tun_set_iff, b'T', 202, libc::c_int);
 ioctl_write_ptr!(tun_set_persist, b'T', 203, libc::c_int);
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum LinkType {
-    #[allow(dead_code)]
     Tun,
     Tap,
+    /// A veth pair with the given peer name. Veth links are backed by
+    /// rtnetlink rather than the tun device, use `create_veth_pair()`.
+    Veth { peer_name: String },
 }
 
 pub fn create_link(name: &str, link_type: LinkType, queues: usize) -> Result<()> {
@@ -89,6 +91,11 @@ pub fn create_link(name: &str, link_type: LinkType, queues: usize) -> Result<()>
     flags |= match link_type {
         LinkType::Tun => libc::IFF_TUN,
         LinkType::Tap => libc::IFF_TAP,
+        LinkType::Veth { .. } => {
+            return Err(anyhow!(
+                "veth links are not tun devices, use create_veth_pair()"
+            ))
+        }
     };
 
     let queues = if queues == 0 { 1 } else { queues };
@@ -117,6 +124,49 @@ pub fn create_link(name: &str, link_type: LinkType, queues: usize) -> Result<()>
     Ok(())
 }
 
+/// Create a veth pair `name`/`peer_name`, e.g. to recreate a CNI-provided
+/// veth inside the sandbox netns, and return the indices of both ends.
+pub async fn create_veth_pair(
+    handle: &rtnetlink::Handle,
+    name: &str,
+    peer_name: &str,
+) -> Result<(u32, u32)> {
+    handle
+        .link()
+        .add()
+        .veth(name.to_string(), peer_name.to_string())
+        .execute()
+        .await
+        .context("create veth pair")?;
+
+    let index = crate::network::network_model::tc_filter_model::fetch_index(handle, name)
+        .await
+        .context("fetch veth index")?;
+    let peer_index = crate::network::network_model::tc_filter_model::fetch_index(handle, peer_name)
+        .await
+        .context("fetch veth peer index")?;
+
+    Ok((index, peer_index))
+}
+
+/// Set the MTU of an existing link, e.g. to enable jumbo frames on the
+/// guest-side tap created by `create_link()`.
+pub async fn set_mtu(handle: &rtnetlink::Handle, name: &str, mtu: u32) -> Result<()> {
+    let link_index = crate::network::network_model::tc_filter_model::fetch_index(handle, name)
+        .await
+        .context("fetch index")?;
+
+    handle
+        .link()
+        .set(link_index)
+        .mtu(mtu)
+        .execute()
+        .await
+        .context("set link mtu")?;
+
+    Ok(())
+}
+
 fn create_queue(name: &str, flags: libc::c_int) -> Result<(File, String)> {
     let path = Path::new(DEVICE_PATH);
     let file = OpenOptions::new().read(true).write(true).open(path)?;
@@ -178,6 +228,12 @@ mod tests {
             assert!(create_link(name_tap, LinkType::Tap, 2).is_ok());
             assert!(get_link_by_name(&handle, name_tap).await.is_ok());
             assert!(get_link_by_name(&handle, name_tun).await.is_ok());
+
+            // jumbo frame MTU applied and read back
+            assert!(set_mtu(&handle, name_tap, 9000).await.is_ok());
+            let tap_link = get_link_by_name(&handle, name_tap).await.unwrap();
+            assert_eq!(tap_link.attrs().mtu, 9000);
+
             assert!(delete_link(&handle, name_tun).await.is_ok());
             assert!(delete_link(&handle, name_tap).await.is_ok());
 
@@ -186,4 +242,38 @@ mod tests {
             assert!(get_link_by_name(&handle, name_tap).await.is_err());
         }
     }
+
+    #[actix_rt::test]
+    async fn test_create_veth_pair() {
+        let name = "___test_vetha";
+        let peer_name = "___test_vethb";
+
+        // tests should be taken under root
+        skip_if_not_root!();
+
+        if let Ok((conn, handle, _)) =
+            rtnetlink::new_connection().context("failed to create netlink connection")
+        {
+            let thread_handler = tokio::spawn(conn);
+            defer!({
+                thread_handler.abort();
+            });
+
+            // a veth is not a tun device, create_link refuses it
+            let link_type = LinkType::Veth {
+                peer_name: peer_name.to_string(),
+            };
+            assert!(create_link(name, link_type, 1).is_err());
+
+            let (index, peer_index) = create_veth_pair(&handle, name, peer_name).await.unwrap();
+            assert_ne!(index, peer_index);
+            assert!(get_link_by_name(&handle, name).await.is_ok());
+            assert!(get_link_by_name(&handle, peer_name).await.is_ok());
+
+            // deleting one end removes the whole pair
+            assert!(delete_link(&handle, name).await.is_ok());
+            assert!(get_link_by_name(&handle, name).await.is_err());
+            assert!(get_link_by_name(&handle, peer_name).await.is_err());
+        }
+    }
 }
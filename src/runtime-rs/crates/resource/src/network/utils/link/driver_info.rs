@@ -6,7 +6,7 @@
 
 use std::{io, mem};
 
-use anyhow::{Context, Result};
+use anyhow::{anyhow, Context, Result};
 use nix::sys::socket::{socket, AddressFamily, SockFlag, SockType};
 use scopeguard::defer;
 
@@ -24,6 +24,15 @@ const ETHTOOL_EROM_VERSION_LEN: usize = 32;
 /// driver info
 const ETHTOOL_DRIVER_INFO: u32 = 0x00000003;
 
+/// set TX checksum offload
+const ETHTOOL_STXCSUM: u32 = 0x00000017;
+
+/// set TCP segmentation offload
+const ETHTOOL_STSO: u32 = 0x0000001f;
+
+/// set generic segmentation offload
+const ETHTOOL_SGSO: u32 = 0x00000024;
+
 /// Ethtool interface define 0x8946
 const IOCTL_ETHTOOL_INTERFACE: u32 = 0x8946;
 
@@ -72,17 +81,58 @@ struct Driver {
     pub regdump_len: u32,
 }
 
+/// TCP segmentation offload
+pub const OFFLOAD_TSO: &str = "tso";
+/// generic segmentation offload
+pub const OFFLOAD_GSO: &str = "gso";
+/// checksum offload
+pub const OFFLOAD_CSUM: &str = "csum";
+
+// Drivers known to implement segmentation offloads. For tap devices backed by
+// other drivers the offloads have to be disabled to avoid guest checksum
+// issues.
+const OFFLOAD_CAPABLE_DRIVERS: &[&str] = &["tun", "veth", "virtio_net"];
+
 #[derive(Debug, Clone)]
 pub struct DriverInfo {
     pub driver: String,
     pub bus_info: String,
 }
 
-pub fn get_driver_info(name: &str) -> Result<DriverInfo> {
+impl DriverInfo {
+    /// Check whether the underlying driver supports the given offload feature.
+    pub fn supports_offload(&self, feature: &str) -> bool {
+        match feature {
+            OFFLOAD_TSO | OFFLOAD_GSO | OFFLOAD_CSUM => {
+                OFFLOAD_CAPABLE_DRIVERS.contains(&self.driver.as_str())
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Return the offload features which have to be disabled on a tap device
+/// because its underlying driver does not support them.
+pub fn offloads_to_disable(driver_info: &DriverInfo) -> Vec<&'static str> {
+    [OFFLOAD_TSO, OFFLOAD_GSO, OFFLOAD_CSUM]
+        .iter()
+        .filter(|feature| !driver_info.supports_offload(feature))
+        .copied()
+        .collect()
+}
+
+/// ethtool set command argument, taking a single on/off value.
+#[repr(C)]
+struct EthtoolValue {
+    cmd: u32,
+    data: u32,
+}
+
+// Issue an ethtool request for the given device, with `data` pointing at the
+// command-specific argument structure.
+fn ethtool_request(name: &str, data: *mut libc::c_char) -> Result<()> {
     let mut req = DeviceInfoReq::from_name(name).context(format!("ifreq from name {}", name))?;
-    let mut ereq: Driver = unsafe { mem::zeroed() };
-    ereq.cmd = ETHTOOL_DRIVER_INFO;
-    req.ifr_ifru.ifr_data = &mut ereq as *mut _ as *mut _;
+    req.ifr_ifru.ifr_data = data;
 
     let fd = socket(
         AddressFamily::Inet,
@@ -95,8 +145,101 @@ pub fn get_driver_info(name: &str) -> Result<DriverInfo> {
         let _ = nix::unistd::close(fd);
     });
     unsafe { ioctl_ethtool(fd, &mut req).context("ioctl ethtool")? };
+    Ok(())
+}
+
+/// Switch the given offload feature on or off on the given device.
+fn set_offload(name: &str, feature: &str, enable: bool) -> Result<()> {
+    let cmd = match feature {
+        OFFLOAD_TSO => ETHTOOL_STSO,
+        OFFLOAD_GSO => ETHTOOL_SGSO,
+        OFFLOAD_CSUM => ETHTOOL_STXCSUM,
+        _ => return Err(anyhow!("unknown offload feature {}", feature)),
+    };
+    let mut ereq = EthtoolValue {
+        cmd,
+        data: enable as u32,
+    };
+    ethtool_request(name, &mut ereq as *mut _ as *mut _)
+        .context(format!("set {} to {} on {}", feature, enable, name))
+}
+
+/// Disable the offload features the driver backing the given device does not
+/// support, returning the features which were switched off.
+pub fn disable_unsupported_offloads(name: &str) -> Result<Vec<&'static str>> {
+    let driver_info = get_driver_info(name).context("get driver info")?;
+    let features = offloads_to_disable(&driver_info);
+    for feature in features.iter() {
+        set_offload(name, feature, false)?;
+    }
+    Ok(features)
+}
+
+pub fn get_driver_info(name: &str) -> Result<DriverInfo> {
+    let mut ereq: Driver = unsafe { mem::zeroed() };
+    ereq.cmd = ETHTOOL_DRIVER_INFO;
+    ethtool_request(name, &mut ereq as *mut _ as *mut _)?;
     Ok(DriverInfo {
         driver: get_name!(ereq.driver).context("get driver name")?,
         bus_info: get_name!(ereq.bus_info).context("get bus info name")?,
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use test_utils::skip_if_not_root;
+
+    use crate::network::utils::link::net_test_utils::delete_link;
+    use crate::network::utils::link::{create_link, LinkType};
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_disable_unsupported_offloads() {
+        let name = "___test_offl";
+
+        // tests should be taken under root
+        skip_if_not_root!();
+
+        if let Ok((conn, handle, _)) =
+            rtnetlink::new_connection().context("failed to create netlink connection")
+        {
+            let thread_handler = tokio::spawn(conn);
+            defer!({
+                thread_handler.abort();
+            });
+
+            assert!(create_link(name, LinkType::Tap, 2).is_ok());
+
+            // taps are backed by the tun driver, which implements every
+            // offload, so nothing has to be switched off
+            let disabled = disable_unsupported_offloads(name).unwrap();
+            assert!(disabled.is_empty());
+
+            assert!(delete_link(&handle, name).await.is_ok());
+        }
+    }
+
+    #[test]
+    fn test_supports_offload() {
+        let tun_info = DriverInfo {
+            driver: "tun".to_string(),
+            bus_info: "tap".to_string(),
+        };
+        assert!(tun_info.supports_offload(OFFLOAD_TSO));
+        assert!(tun_info.supports_offload(OFFLOAD_GSO));
+        assert!(tun_info.supports_offload(OFFLOAD_CSUM));
+        assert!(!tun_info.supports_offload("unknown-feature"));
+        assert!(offloads_to_disable(&tun_info).is_empty());
+
+        let legacy_info = DriverInfo {
+            driver: "e1000".to_string(),
+            bus_info: "0000:00:03.0".to_string(),
+        };
+        assert!(!legacy_info.supports_offload(OFFLOAD_TSO));
+        assert_eq!(
+            offloads_to_disable(&legacy_info),
+            vec![OFFLOAD_TSO, OFFLOAD_GSO, OFFLOAD_CSUM]
+        );
+    }
+}
@@ -5,9 +5,12 @@
 //
 
 mod create;
-pub use create::{create_link, LinkType};
+pub use create::{create_link, create_veth_pair, set_mtu, LinkType};
 mod driver_info;
-pub use driver_info::get_driver_info;
+pub use driver_info::{
+    disable_unsupported_offloads, get_driver_info, offloads_to_disable, DriverInfo, OFFLOAD_CSUM,
+    OFFLOAD_GSO, OFFLOAD_TSO,
+};
 mod macros;
 mod manager;
 pub use manager::get_link_from_message;
@@ -5,7 +5,7 @@
 //
 
 pub(crate) mod address;
-pub(crate) mod link;
+pub mod link;
 
 use anyhow::{anyhow, Result};
 use rand::rngs::OsRng;
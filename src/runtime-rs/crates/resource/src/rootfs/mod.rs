@@ -99,35 +99,26 @@ impl RootFsResource {
                             .context("new block rootfs")?,
                     );
                     Ok(block_rootfs)
-                } else if let Some(share_fs) = share_fs {
-                    // handle nydus rootfs
-                    let share_rootfs: Arc<dyn Rootfs> = if layer.fs_type == NYDUS_ROOTFS_TYPE {
-                        Arc::new(
-                            nydus_rootfs::NydusRootfs::new(
-                                device_manager,
-                                share_fs,
-                                h,
-                                sid,
-                                cid,
-                                layer,
-                            )
+                } else if layer.fs_type == NYDUS_ROOTFS_TYPE {
+                    // handle nydus rootfs, it needs a share-fs to export the
+                    // rafs bootstrap to the guest, so fail early with a clear
+                    // error instead of inside the guest later.
+                    let share_fs = share_fs.as_ref().ok_or_else(|| {
+                        anyhow!("nydus rootfs requires share fs, but it is unavailable")
+                    })?;
+                    let nydus_rootfs: Arc<dyn Rootfs> = Arc::new(
+                        nydus_rootfs::NydusRootfs::new(device_manager, share_fs, h, sid, cid, layer)
                             .await
                             .context("new nydus rootfs")?,
-                        )
-                    }
+                    );
+                    Ok(nydus_rootfs)
+                } else if let Some(share_fs) = share_fs {
                     // handle sharefs rootfs
-                    else {
-                        Arc::new(
-                            share_fs_rootfs::ShareFsRootfs::new(
-                                share_fs,
-                                cid,
-                                bundle_path,
-                                Some(layer),
-                            )
+                    let share_rootfs: Arc<dyn Rootfs> = Arc::new(
+                        share_fs_rootfs::ShareFsRootfs::new(share_fs, cid, bundle_path, Some(layer))
                             .await
                             .context("new share fs rootfs")?,
-                        )
-                    };
+                    );
                     Ok(share_rootfs)
                 } else {
                     Err(anyhow!("unsupported rootfs {:?}", &layer))
@@ -142,19 +133,167 @@ impl RootFsResource {
         }
     }
 
-    pub async fn dump(&self) {
+    /// Take a structured snapshot of the managed rootfs entries for
+    /// diagnostics tooling.
+    pub async fn snapshot(&self) -> Vec<RootfsSnapshot> {
         let inner = self.inner.read().await;
+        let mut snapshots = Vec::with_capacity(inner.rootfs.len());
         for r in &inner.rootfs {
+            snapshots.push(RootfsSnapshot {
+                guest_path: r.get_guest_rootfs_path().await.unwrap_or_default(),
+                has_storage: r.get_storage().await.is_some(),
+                refcount: Arc::strong_count(r),
+            });
+        }
+        snapshots
+    }
+
+    pub async fn dump(&self) {
+        for s in self.snapshot().await {
             info!(
                 sl!(),
-                "rootfs {:?}: count {}",
-                r.get_guest_rootfs_path().await,
-                Arc::strong_count(r)
+                "rootfs {}: storage {} count {}", s.guest_path, s.has_storage, s.refcount
             );
         }
     }
 }
 
+/// Serializable snapshot of a single rootfs entry managed by [`RootFsResource`].
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct RootfsSnapshot {
+    /// Rootfs path inside the guest.
+    pub guest_path: String,
+    /// Whether the rootfs is backed by an agent storage object.
+    pub has_storage: bool,
+    /// Number of strong references held on the rootfs.
+    pub refcount: usize,
+}
+
 fn is_single_layer_rootfs(rootfs_mounts: &[Mount]) -> bool {
     rootfs_mounts.len() == 1
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::{anyhow, Context, Result};
+    use hypervisor::{device::device_manager::DeviceManager, qemu::Qemu, Hypervisor};
+    use kata_types::config::hypervisor::TopologyConfigInfo;
+    use kata_types::mount::Mount;
+    use tests_utils::load_test_config;
+    use tokio::sync::RwLock;
+
+    use agent::Storage;
+    use async_trait::async_trait;
+
+    use super::{nydus_rootfs::NYDUS_ROOTFS_TYPE, RootFsResource, Rootfs};
+
+    async fn get_hypervisor() -> Result<Qemu> {
+        let hypervisor_name: &str = "qemu";
+        let toml_config = load_test_config(hypervisor_name.to_owned())?;
+        let hypervisor_config = toml_config
+            .hypervisor
+            .get(hypervisor_name)
+            .ok_or_else(|| anyhow!("failed to get hypervisor for {}", &hypervisor_name))?;
+
+        let mut hypervisor = Qemu::new();
+        hypervisor
+            .set_hypervisor_config(hypervisor_config.clone())
+            .await;
+
+        Ok(hypervisor)
+    }
+
+    #[actix_rt::test]
+    async fn test_nydus_rootfs_requires_share_fs() {
+        let hypervisor = get_hypervisor().await.unwrap();
+        let toml_config = load_test_config("qemu".to_owned()).unwrap();
+        let topo_config = TopologyConfigInfo::new(&toml_config);
+        let hypervisor = Arc::new(hypervisor);
+        let dm = Arc::new(RwLock::new(
+            DeviceManager::new(hypervisor.clone(), topo_config.as_ref())
+                .await
+                .context("device manager")
+                .unwrap(),
+        ));
+
+        let rootfs_mounts = vec![Mount {
+            fs_type: NYDUS_ROOTFS_TYPE.to_string(),
+            ..Default::default()
+        }];
+
+        let resource = RootFsResource::new();
+        let result = resource
+            .handler_rootfs(
+                &None,
+                &dm,
+                hypervisor.as_ref() as &dyn Hypervisor,
+                "sid",
+                "cid",
+                &oci::Root::default(),
+                "bundle-path",
+                &rootfs_mounts,
+            )
+            .await;
+        let err = match result {
+            Ok(_) => panic!("nydus rootfs without share fs should be rejected"),
+            Err(e) => e,
+        };
+        assert!(err.to_string().contains("requires share fs"));
+    }
+
+    struct FakeRootfs {
+        guest_path: String,
+        storage: Option<Storage>,
+    }
+
+    #[async_trait]
+    impl Rootfs for FakeRootfs {
+        async fn get_guest_rootfs_path(&self) -> Result<String> {
+            Ok(self.guest_path.clone())
+        }
+
+        async fn get_rootfs_mount(&self) -> Result<Vec<oci::Mount>> {
+            Ok(vec![])
+        }
+
+        async fn get_storage(&self) -> Option<Storage> {
+            self.storage.clone()
+        }
+
+        async fn cleanup(&self, _device_manager: &RwLock<DeviceManager>) -> Result<()> {
+            Ok(())
+        }
+
+        async fn get_device_id(&self) -> Result<Option<String>> {
+            Ok(None)
+        }
+    }
+
+    #[actix_rt::test]
+    async fn test_rootfs_snapshot() {
+        let resource = RootFsResource::new();
+        assert!(resource.snapshot().await.is_empty());
+
+        resource.inner.write().await.rootfs.push(Arc::new(FakeRootfs {
+            guest_path: "/run/kata-containers/cid/rootfs".to_string(),
+            storage: Some(Storage::default()),
+        }));
+        resource.inner.write().await.rootfs.push(Arc::new(FakeRootfs {
+            guest_path: "/run/kata-containers/cid2/rootfs".to_string(),
+            storage: None,
+        }));
+
+        let snapshots = resource.snapshot().await;
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!(snapshots[0].guest_path, "/run/kata-containers/cid/rootfs");
+        assert!(snapshots[0].has_storage);
+        assert_eq!(snapshots[0].refcount, 1);
+        assert_eq!(snapshots[1].guest_path, "/run/kata-containers/cid2/rootfs");
+        assert!(!snapshots[1].has_storage);
+
+        // the snapshot is serializable for diagnostics tooling
+        assert!(serde_json::to_string(&snapshots).is_ok());
+    }
+}
@@ -46,12 +46,20 @@ impl NydusRootfs {
         cid: &str,
         rootfs: &Mount,
     ) -> Result<Self> {
+        let hypervisor_config = h.hypervisor_config().await;
         let prefetch_list_path =
-            get_nydus_prefetch_files(h.hypervisor_config().await.prefetch_list_path).await;
+            get_nydus_prefetch_files(hypervisor_config.prefetch_list_path.clone()).await;
 
         let share_fs_mount = share_fs.get_share_fs_mount();
-        let extra_options =
+        let mut extra_options =
             NydusExtraOptions::new(rootfs).context("failed to parse nydus extra options")?;
+        let blob_cache_dir = &hypervisor_config.shared_fs.nydus_blob_cache_dir;
+        if !blob_cache_dir.is_empty() {
+            fs::create_dir_all(blob_cache_dir)
+                .with_context(|| format!("failed to create blob cache dir {}", blob_cache_dir))?;
+            extra_options.config = apply_blob_cache_dir(&extra_options.config, blob_cache_dir)
+                .context("failed to apply blob cache dir")?;
+        }
         info!(sl!(), "extra_option {:?}", &extra_options);
         let rafs_meta = &extra_options.source;
         let (rootfs_storage, rootfs_guest_path) = match extra_options.fs_version.as_str() {
@@ -164,6 +172,16 @@ impl Rootfs for NydusRootfs {
     }
 }
 
+// Rewrite the blob cache working directory in the rafs config content coming
+// from the snapshotter, so that operators can point the blob cache at faster
+// storage via the runtime configuration.
+fn apply_blob_cache_dir(config_content: &str, cache_dir: &str) -> Result<String> {
+    let mut config: serde_json::Value =
+        serde_json::from_str(config_content).context("parse rafs config content")?;
+    config["device"]["cache"]["config"]["work_dir"] = serde_json::Value::from(cache_dir);
+    serde_json::to_string(&config).context("serialize rafs config content")
+}
+
 // Check prefetch files list path, and if invalid, discard it directly.
 // As the result of caller `rafs_mount`, it returns `Option<String>`.
 async fn get_nydus_prefetch_files(nydus_prefetch_path: String) -> Option<String> {
@@ -226,4 +244,30 @@ mod tests {
         drop(file);
         temp_dir.close().unwrap_or_default();
     }
+
+    #[test]
+    fn test_apply_blob_cache_dir() {
+        let config_content = r#"{
+            "device": {
+                "backend": { "type": "registry" },
+                "cache": {
+                    "type": "blobcache",
+                    "config": { "work_dir": "/var/lib/nydus/cache" }
+                }
+            },
+            "mode": "direct"
+        }"#;
+
+        let patched = apply_blob_cache_dir(config_content, "/mnt/nvme/nydus-cache").unwrap();
+        let config: serde_json::Value = serde_json::from_str(&patched).unwrap();
+        assert_eq!(
+            config["device"]["cache"]["config"]["work_dir"],
+            "/mnt/nvme/nydus-cache"
+        );
+        // unrelated fields are left untouched
+        assert_eq!(config["device"]["cache"]["type"], "blobcache");
+        assert_eq!(config["mode"], "direct");
+
+        assert!(apply_blob_cache_dir("not-json", "/mnt/nvme/nydus-cache").is_err());
+    }
 }
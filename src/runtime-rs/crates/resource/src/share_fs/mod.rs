@@ -81,6 +81,8 @@ pub struct ShareFsVolumeConfig {
     pub mount_options: Vec<String>,
     pub mount: oci::Mount,
     pub is_rafs: bool,
+    /// Attach ordering of the mount, lower values attach earlier.
+    pub priority: i32,
 }
 
 pub struct ShareFsMountResult {
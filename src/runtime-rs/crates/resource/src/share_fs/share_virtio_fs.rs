@@ -57,6 +57,7 @@ pub(crate) async fn prepare_virtiofs(
         queue_size: 0,
         queue_num: 0,
         options: vec![],
+        dax_cache_size: None,
         mount_config: None,
     };
 
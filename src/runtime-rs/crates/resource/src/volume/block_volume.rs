@@ -41,6 +41,7 @@ impl BlockVolume {
             major: stat::major(fstat.st_rdev) as i64,
             minor: stat::minor(fstat.st_rdev) as i64,
             driver_option: block_driver,
+            is_readonly: read_only,
             ..Default::default()
         };
 
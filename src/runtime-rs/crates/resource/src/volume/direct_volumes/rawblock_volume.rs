@@ -61,6 +61,7 @@ impl RawblockVolume {
         let block_config = BlockConfig {
             path_on_host: mount_info.device.clone(),
             driver_option: block_driver,
+            is_readonly: read_only,
             ..Default::default()
         };
 
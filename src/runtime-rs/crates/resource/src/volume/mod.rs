@@ -61,10 +61,12 @@ impl VolumeResource {
         agent: Arc<dyn Agent>,
     ) -> Result<Vec<Arc<dyn Volume>>> {
         let mut volumes: Vec<Arc<dyn Volume>> = vec![];
-        let oci_mounts = &spec.mounts;
+        // attach share-fs mounts in priority order so that a mount is set
+        // up before any mount nested under it
+        let oci_mounts = share_fs_volume::order_mounts_by_priority(&spec.mounts);
         info!(sl!(), " oci mount is : {:?}", oci_mounts.clone());
         // handle mounts
-        for m in oci_mounts {
+        for m in &oci_mounts {
             let read_only = m.options.iter().any(|opt| opt == "ro");
             let volume: Arc<dyn Volume> = if shm_volume::is_shm_volume(m) {
                 let shm_size = shm_volume::DEFAULT_SHM_SIZE;
@@ -188,6 +188,7 @@ impl ShareFsVolume {
                             mount_options: m.options.clone(),
                             mount: m.clone(),
                             is_rafs: false,
+                            priority: mount_priority(&m.destination),
                         })
                         .await
                         .context("mount shared volume")?;
@@ -297,6 +298,37 @@ impl Volume for ShareFsVolume {
     }
 }
 
+// Mount priority of a share-fs mount, lower values attach earlier. The
+// priority is derived from the nesting depth of the destination, so a
+// mount is always attached before any mount nested under it.
+pub(crate) fn mount_priority(destination: &str) -> i32 {
+    Path::new(destination).components().count() as i32
+}
+
+// Reorder the share-fs mounts among themselves by priority, keeping every
+// other mount at its original position.
+pub(crate) fn order_mounts_by_priority(oci_mounts: &[oci::Mount]) -> Vec<oci::Mount> {
+    let mut share_fs_mounts: Vec<oci::Mount> = oci_mounts
+        .iter()
+        .filter(|m| is_share_fs_volume(m))
+        .cloned()
+        .collect();
+    share_fs_mounts.sort_by_key(|m| mount_priority(&m.destination));
+
+    let mut ordered = share_fs_mounts.into_iter();
+    oci_mounts
+        .iter()
+        .map(|m| {
+            if is_share_fs_volume(m) {
+                // the i-th share-fs slot takes the i-th mount by priority
+                ordered.next().unwrap()
+            } else {
+                m.clone()
+            }
+        })
+        .collect()
+}
+
 pub(crate) fn is_share_fs_volume(m: &oci::Mount) -> bool {
     (m.r#type == "bind" || m.r#type == mount::KATA_EPHEMERAL_VOLUME_TYPE)
         && !is_host_device(&m.destination)
@@ -370,4 +402,37 @@ mod test {
         assert!(is_system_mount(proc_sub_dir));
         assert!(!is_system_mount(not_sys_dir));
     }
+
+    #[test]
+    fn test_order_mounts_by_priority() {
+        let bind_mount = |destination: &str| oci::Mount {
+            destination: destination.to_string(),
+            r#type: "bind".to_string(),
+            source: "/root/data".to_string(),
+            options: vec![],
+        };
+        let mounts = vec![
+            bind_mount("/nested/a/b"),
+            // not a share-fs mount, must keep its position
+            oci::Mount {
+                destination: "/proc".to_string(),
+                r#type: "proc".to_string(),
+                source: "proc".to_string(),
+                options: vec![],
+            },
+            bind_mount("/nested"),
+            bind_mount("/nested/a"),
+        ];
+
+        // a parent directory has a lower priority value than its children
+        assert!(mount_priority("/nested") < mount_priority("/nested/a"));
+        assert!(mount_priority("/nested/a") < mount_priority("/nested/a/b"));
+
+        let ordered = order_mounts_by_priority(&mounts);
+        let destinations: Vec<&str> = ordered.iter().map(|m| m.destination.as_str()).collect();
+        assert_eq!(
+            destinations,
+            vec!["/nested", "/proc", "/nested/a", "/nested/a/b"]
+        );
+    }
 }
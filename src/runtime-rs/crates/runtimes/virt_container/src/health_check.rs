@@ -4,10 +4,12 @@
 // SPDX-License-Identifier: Apache-2.0
 //
 
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
-use agent::Agent;
-use anyhow::Context;
+use agent::{Agent, HealthCheckResponse};
+use anyhow::{Context, Result};
 use tokio::sync::{mpsc, Mutex};
 
 /// monitor check interval 30s
@@ -19,6 +21,60 @@ const VERSION_CHECK_THRESHOLD: u64 = 5 * 60 / HEALTH_CHECK_TIMER_INTERVAL;
 /// health check stop channel buffer size
 const HEALTH_CHECK_STOP_CHANNEL_BUFFER_SIZE: usize = 1;
 
+/// the agent's gRPC health service reports SERVING as 1
+const SERVING_STATUS_SERVING: u32 = 1;
+
+/// number of retries for a transient agent-unreachable failure
+const TRANSIENT_FAILURE_RETRIES: u32 = 3;
+
+/// base backoff between retries of a transient failure
+const TRANSIENT_RETRY_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Outcome of probing the agent for the sandbox health.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SandboxHealth {
+    /// The agent answered and reports the guest as serving.
+    Healthy,
+    /// The agent could not be reached at all; most likely a transient vsock
+    /// hiccup, worth retrying before declaring the sandbox dead.
+    AgentUnreachable,
+    /// The agent answered but explicitly reported the guest unhealthy with
+    /// the given serving status; retrying will not help.
+    GuestUnhealthy(u32),
+}
+
+/// Classify the result of a single agent check call.
+fn classify(result: Result<HealthCheckResponse>) -> SandboxHealth {
+    match result {
+        Ok(resp) => {
+            if resp.status == SERVING_STATUS_SERVING {
+                SandboxHealth::Healthy
+            } else {
+                SandboxHealth::GuestUnhealthy(resp.status)
+            }
+        }
+        Err(_) => SandboxHealth::AgentUnreachable,
+    }
+}
+
+/// Probe the agent through the given check function, retrying transient
+/// unreachable failures with a linear backoff. An explicit guest-unhealthy
+/// response escalates immediately without further retries.
+async fn probe_with_retry<F, Fut>(mut check: F, backoff: Duration) -> SandboxHealth
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<HealthCheckResponse>>,
+{
+    let mut health = classify(check().await);
+    let mut retries = 0;
+    while health == SandboxHealth::AgentUnreachable && retries < TRANSIENT_FAILURE_RETRIES {
+        retries += 1;
+        tokio::time::sleep(backoff * retries).await;
+        health = classify(check().await);
+    }
+    health
+}
+
 pub struct HealthCheck {
     pub keep_alive: bool,
     keep_abnormal: bool,
@@ -37,6 +93,20 @@ impl HealthCheck {
         }
     }
 
+    /// Probe the agent, retrying transient unreachable failures with a
+    /// linear backoff; callers get a typed outcome instead of a bare error.
+    pub async fn probe(agent: &Arc<dyn Agent>) -> SandboxHealth {
+        let agent = agent.clone();
+        probe_with_retry(
+            move || {
+                let agent = agent.clone();
+                async move { agent.check(agent::CheckRequest::new("")).await }
+            },
+            TRANSIENT_RETRY_BACKOFF,
+        )
+        .await
+    }
+
     pub fn start(&self, id: &str, agent: Arc<dyn Agent>) {
         if !self.keep_alive {
             return;
@@ -62,12 +132,8 @@ impl HealthCheck {
 
                     Err(mpsc::error::TryRecvError::Empty) => {
                         // check agent
-                        match agent
-                            .check(agent::CheckRequest::new(""))
-                            .await
-                            .context("check health")
-                        {
-                            Ok(_) => {
+                        match Self::probe(&agent).await {
+                            SandboxHealth::Healthy => {
                                 debug!(sl!(), "check {} agent health successfully", id);
                                 version_check_threshold_count += 1;
                                 if version_check_threshold_count >= VERSION_CHECK_THRESHOLD {
@@ -83,8 +149,21 @@ impl HealthCheck {
                                 }
                                 continue;
                             }
-                            Err(e) => {
-                                error!(sl!(), "failed to do {} agent health check: {}", id, e);
+                            health => {
+                                match health {
+                                    SandboxHealth::AgentUnreachable => error!(
+                                        sl!(),
+                                        "failed to reach {} agent for health check after retries",
+                                        id
+                                    ),
+                                    SandboxHealth::GuestUnhealthy(status) => error!(
+                                        sl!(),
+                                        "{} agent explicitly reports guest unhealthy, status {}",
+                                        id,
+                                        status
+                                    ),
+                                    SandboxHealth::Healthy => unreachable!(),
+                                }
                                 if let Err(mpsc::error::TryRecvError::Empty) = stop_rx.try_recv() {
                                     error!(sl!(), "failed to receive stop monitor signal");
                                     if !keep_abnormal {
@@ -121,3 +200,92 @@ impl HealthCheck {
             .ok();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use anyhow::anyhow;
+
+    use super::*;
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(
+            classify(Ok(HealthCheckResponse {
+                status: SERVING_STATUS_SERVING
+            })),
+            SandboxHealth::Healthy
+        );
+        assert_eq!(
+            classify(Ok(HealthCheckResponse { status: 2 })),
+            SandboxHealth::GuestUnhealthy(2)
+        );
+        assert_eq!(
+            classify(Err(anyhow!("connection reset"))),
+            SandboxHealth::AgentUnreachable
+        );
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_recovers() {
+        let calls = AtomicU32::new(0);
+
+        // the agent is unreachable twice before answering healthy
+        let health = probe_with_retry(
+            || {
+                let call = calls.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    if call < 2 {
+                        Err(anyhow!("vsock connection refused"))
+                    } else {
+                        Ok(HealthCheckResponse {
+                            status: SERVING_STATUS_SERVING,
+                        })
+                    }
+                }
+            },
+            Duration::from_millis(0),
+        )
+        .await;
+
+        assert_eq!(health, SandboxHealth::Healthy);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_gives_up_after_retries() {
+        let calls = AtomicU32::new(0);
+
+        let health = probe_with_retry(
+            || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Err(anyhow!("vsock connection refused")) }
+            },
+            Duration::from_millis(0),
+        )
+        .await;
+
+        assert_eq!(health, SandboxHealth::AgentUnreachable);
+        // the first probe plus the configured number of retries
+        assert_eq!(calls.load(Ordering::SeqCst), 1 + TRANSIENT_FAILURE_RETRIES);
+    }
+
+    #[tokio::test]
+    async fn test_guest_unhealthy_escalates_immediately() {
+        let calls = AtomicU32::new(0);
+
+        let health = probe_with_retry(
+            || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                async { Ok(HealthCheckResponse { status: 2 }) }
+            },
+            Duration::from_millis(0),
+        )
+        .await;
+
+        // an explicit unhealthy response is not retried
+        assert_eq!(health, SandboxHealth::GuestUnhealthy(2));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
@@ -13,8 +13,12 @@ mod container_manager;
 pub mod health_check;
 pub mod sandbox;
 pub mod sandbox_persist;
+pub mod vm_factory;
 
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
 
 use agent::{kata::KataAgent, AGENT_KATA};
 use anyhow::{anyhow, Context, Result};
@@ -26,19 +30,29 @@ use hypervisor::{dragonball::Dragonball, HYPERVISOR_DRAGONBALL};
 use hypervisor::{qemu::Qemu, HYPERVISOR_QEMU};
 #[cfg(not(target_arch = "s390x"))]
 use kata_types::config::DragonballConfig;
-use kata_types::config::{hypervisor::register_hypervisor_plugin, QemuConfig, TomlConfig};
+use kata_types::config::{
+    hypervisor::{get_hypervisor_plugin, register_hypervisor_plugin},
+    QemuConfig, TomlConfig,
+};
 
 #[cfg(all(feature = "cloud-hypervisor", not(target_arch = "s390x")))]
 use hypervisor::ch::CloudHypervisor;
 #[cfg(all(feature = "cloud-hypervisor", not(target_arch = "s390x")))]
 use kata_types::config::{hypervisor::HYPERVISOR_NAME_CH, CloudHypervisorConfig};
 
+use lazy_static::lazy_static;
 use resource::cpu_mem::initial_size::InitialSizeManager;
 use resource::ResourceManager;
 use sandbox::VIRTCONTAINER;
 use tokio::sync::mpsc::Sender;
 use tracing::instrument;
 
+// Derive a sandbox-scoped child logger from the subsystem logger, so that
+// logs from concurrent sandboxes in one shim process can be told apart.
+fn sandbox_logger(sid: &str) -> slog::Logger {
+    sl!().new(o!("sid" => sid.to_string()))
+}
+
 unsafe impl Send for VirtContainer {}
 unsafe impl Sync for VirtContainer {}
 #[derive(Debug)]
@@ -55,15 +69,28 @@ impl RuntimeHandler for VirtContainer {
         {
             let dragonball_config = Arc::new(DragonballConfig::new());
             register_hypervisor_plugin("dragonball", dragonball_config);
+            register_hypervisor_constructor(HYPERVISOR_DRAGONBALL, new_dragonball_hypervisor);
         }
 
         let qemu_config = Arc::new(QemuConfig::new());
         register_hypervisor_plugin("qemu", qemu_config);
+        register_hypervisor_constructor(HYPERVISOR_QEMU, new_qemu_hypervisor);
 
         #[cfg(all(feature = "cloud-hypervisor", not(target_arch = "s390x")))]
         {
             let ch_config = Arc::new(CloudHypervisorConfig::new());
             register_hypervisor_plugin(HYPERVISOR_NAME_CH, ch_config);
+            register_hypervisor_constructor(HYPERVISOR_NAME_CH, new_cloud_hypervisor);
+        }
+
+        // Registration goes through a global registry, so verify that every
+        // supported plugin can actually be looked up. A failure here is far
+        // easier to diagnose than the "unsupported hypervisor" error a missing
+        // plugin would cause at instance creation time.
+        for name in SUPPORTED_HYPERVISORS {
+            if get_hypervisor_plugin(name).is_none() {
+                return Err(anyhow!("failed to register hypervisor plugin {}", name));
+            }
         }
 
         Ok(())
@@ -85,7 +112,19 @@ impl RuntimeHandler for VirtContainer {
         config: Arc<TomlConfig>,
         init_size_manager: InitialSizeManager,
     ) -> Result<RuntimeInstance> {
-        let hypervisor = new_hypervisor(&config).await.context("new hypervisor")?;
+        let logger = sandbox_logger(sid);
+        validate_hypervisor_name(&config).context("validate hypervisor name")?;
+
+        let factory = vm_factory::VMFactory::new(Arc::new(vm_factory::Direct::new()));
+        let vm = factory
+            .get_base_vm(&config)
+            .await
+            .context("get base vm from factory")?;
+        vm.validate(&config)
+            .await
+            .context("validate base vm against config")?;
+        let hypervisor = vm.hypervisor();
+        info!(logger, "got base vm from factory");
 
         // get uds from hypervisor and get config from toml_config
         let agent = new_agent(&config).context("new agent")?;
@@ -101,15 +140,25 @@ impl RuntimeHandler for VirtContainer {
         );
         let pid = std::process::id();
 
-        let sandbox = sandbox::VirtSandbox::new(
+        let sandbox = match sandbox::VirtSandbox::new(
             sid,
             msg_sender,
             agent.clone(),
             hypervisor.clone(),
             resource_manager.clone(),
+            Some(factory.inner()),
         )
         .await
-        .context("new virt sandbox")?;
+        {
+            Ok(sandbox) => sandbox,
+            Err(e) => {
+                // nothing owns the base VM yet, release it with the factory
+                if let Err(close_err) = factory.close().await {
+                    warn!(logger, "failed to close vm factory: {:?}", close_err);
+                }
+                return Err(e).context("new virt sandbox");
+            }
+        };
         let container_manager = container_manager::VirtContainerManager::new(
             sid,
             pid,
@@ -117,6 +166,7 @@ impl RuntimeHandler for VirtContainer {
             hypervisor,
             resource_manager,
         );
+        info!(logger, "created sandbox and container manager");
         Ok(RuntimeInstance {
             sandbox: Arc::new(sandbox),
             container_manager: Arc::new(container_manager),
@@ -129,50 +179,123 @@ impl RuntimeHandler for VirtContainer {
     }
 }
 
-async fn new_hypervisor(toml_config: &TomlConfig) -> Result<Arc<dyn Hypervisor>> {
+/// Hypervisors this runtime is built to drive.
+const SUPPORTED_HYPERVISORS: &[&str] = &[
+    #[cfg(not(target_arch = "s390x"))]
+    HYPERVISOR_DRAGONBALL,
+    HYPERVISOR_QEMU,
+    #[cfg(all(feature = "cloud-hypervisor", not(target_arch = "s390x")))]
+    HYPERVISOR_NAME_CH,
+];
+
+// Check the configured hypervisor name up front, so that a typo in the
+// configuration fails at instance creation with an actionable message instead
+// of deep inside hypervisor construction.
+fn validate_hypervisor_name(toml_config: &TomlConfig) -> Result<()> {
+    let hypervisor_name = &toml_config.runtime.hypervisor_name;
+    if !SUPPORTED_HYPERVISORS.contains(&hypervisor_name.as_str()) {
+        return Err(anyhow!(
+            "unsupported hypervisor {:?}, please set runtime.hypervisor_name to one of {:?}",
+            hypervisor_name,
+            SUPPORTED_HYPERVISORS
+        ));
+    }
+    if !toml_config.hypervisor.contains_key(hypervisor_name) {
+        return Err(anyhow!(
+            "missing [hypervisor.{}] section for configured hypervisor {:?}",
+            hypervisor_name,
+            hypervisor_name
+        ));
+    }
+    Ok(())
+}
+
+/// Constructor building a hypervisor instance from the runtime config.
+type NewHypervisorFn =
+    for<'a> fn(
+        &'a TomlConfig,
+    ) -> Pin<Box<dyn Future<Output = Result<Arc<dyn Hypervisor>>> + Send + 'a>>;
+
+lazy_static! {
+    // Registry of hypervisor constructors keyed by hypervisor name, populated
+    // at init time, so that supporting a new hypervisor does not require
+    // touching new_hypervisor() itself.
+    static ref HYPERVISOR_REGISTRY: Mutex<HashMap<String, NewHypervisorFn>> =
+        Mutex::new(HashMap::new());
+}
+
+fn register_hypervisor_constructor(name: &str, constructor: NewHypervisorFn) {
+    let mut registry = HYPERVISOR_REGISTRY.lock().unwrap();
+    registry.insert(name.to_string(), constructor);
+}
+
+fn get_hypervisor_config<'a>(
+    toml_config: &'a TomlConfig,
+) -> Result<&'a kata_types::config::hypervisor::Hypervisor> {
     let hypervisor_name = &toml_config.runtime.hypervisor_name;
-    let hypervisor_config = toml_config
+    toml_config
         .hypervisor
         .get(hypervisor_name)
         .ok_or_else(|| anyhow!("failed to get hypervisor for {}", &hypervisor_name))
-        .context("get hypervisor")?;
+        .context("get hypervisor")
+}
 
-    // TODO: support other hypervisor
-    // issue: https://github.com/kata-containers/kata-containers/issues/4634
-    match hypervisor_name.as_str() {
-        #[cfg(not(target_arch = "s390x"))]
-        HYPERVISOR_DRAGONBALL => {
-            let mut hypervisor = Dragonball::new();
-            hypervisor
-                .set_hypervisor_config(hypervisor_config.clone())
-                .await;
-            if toml_config.runtime.use_passfd_io {
-                hypervisor
-                    .set_passfd_listener_port(toml_config.runtime.passfd_listener_port)
-                    .await;
-            }
-            Ok(Arc::new(hypervisor))
-        }
-        HYPERVISOR_QEMU => {
-            let mut hypervisor = Qemu::new();
+#[cfg(not(target_arch = "s390x"))]
+fn new_dragonball_hypervisor(
+    toml_config: &TomlConfig,
+) -> Pin<Box<dyn Future<Output = Result<Arc<dyn Hypervisor>>> + Send + '_>> {
+    Box::pin(async move {
+        let hypervisor_config = get_hypervisor_config(toml_config)?;
+        let mut hypervisor = Dragonball::new();
+        hypervisor
+            .set_hypervisor_config(hypervisor_config.clone())
+            .await;
+        if toml_config.runtime.use_passfd_io {
             hypervisor
-                .set_hypervisor_config(hypervisor_config.clone())
+                .set_passfd_listener_port(toml_config.runtime.passfd_listener_port)
                 .await;
-            Ok(Arc::new(hypervisor))
         }
+        Ok(Arc::new(hypervisor) as Arc<dyn Hypervisor>)
+    })
+}
 
-        #[cfg(all(feature = "cloud-hypervisor", not(target_arch = "s390x")))]
-        HYPERVISOR_NAME_CH => {
-            let mut hypervisor = CloudHypervisor::new();
+fn new_qemu_hypervisor(
+    toml_config: &TomlConfig,
+) -> Pin<Box<dyn Future<Output = Result<Arc<dyn Hypervisor>>> + Send + '_>> {
+    Box::pin(async move {
+        let hypervisor_config = get_hypervisor_config(toml_config)?;
+        let mut hypervisor = Qemu::new();
+        hypervisor
+            .set_hypervisor_config(hypervisor_config.clone())
+            .await;
+        Ok(Arc::new(hypervisor) as Arc<dyn Hypervisor>)
+    })
+}
 
-            hypervisor
-                .set_hypervisor_config(hypervisor_config.clone())
-                .await;
+#[cfg(all(feature = "cloud-hypervisor", not(target_arch = "s390x")))]
+fn new_cloud_hypervisor(
+    toml_config: &TomlConfig,
+) -> Pin<Box<dyn Future<Output = Result<Arc<dyn Hypervisor>>> + Send + '_>> {
+    Box::pin(async move {
+        let hypervisor_config = get_hypervisor_config(toml_config)?;
+        let mut hypervisor = CloudHypervisor::new();
+        hypervisor
+            .set_hypervisor_config(hypervisor_config.clone())
+            .await;
+        Ok(Arc::new(hypervisor) as Arc<dyn Hypervisor>)
+    })
+}
 
-            Ok(Arc::new(hypervisor))
-        }
-        _ => Err(anyhow!("Unsupported hypervisor {}", &hypervisor_name)),
-    }
+async fn new_hypervisor(toml_config: &TomlConfig) -> Result<Arc<dyn Hypervisor>> {
+    let hypervisor_name = &toml_config.runtime.hypervisor_name;
+    let constructor = HYPERVISOR_REGISTRY
+        .lock()
+        .unwrap()
+        .get(hypervisor_name)
+        .copied()
+        .ok_or_else(|| anyhow!("Unsupported hypervisor {}", &hypervisor_name))?;
+
+    constructor(toml_config).await
 }
 
 fn new_agent(toml_config: &TomlConfig) -> Result<Arc<KataAgent>> {
@@ -237,4 +360,136 @@ hypervisor_name="qemu"
         let res = new_hypervisor(&toml_config).await;
         assert!(res.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_new_hypervisor_from_registry() {
+        VirtContainer::init().unwrap();
+
+        let mut toml_config = {
+            let config_content = r#"
+[hypervisor.qemu]
+path = "/bin/echo"
+kernel = "/bin/echo"
+image = "/bin/echo"
+firmware = ""
+
+[runtime]
+hypervisor_name="qemu"
+"#;
+            TomlConfig::load(config_content)
+                .map_err(|e| anyhow!("can not load config toml: {}", e))
+                .unwrap()
+        };
+
+        // a name that is not registered cannot be resolved
+        toml_config.runtime.hypervisor_name = "fake-hypervisor".to_string();
+        assert!(new_hypervisor(&toml_config).await.is_err());
+
+        // after registering a constructor under that name, resolution succeeds
+        // without touching new_hypervisor itself
+        register_hypervisor_constructor("fake-hypervisor", |toml_config| {
+            Box::pin(async move {
+                let hypervisor_config = toml_config
+                    .hypervisor
+                    .get("qemu")
+                    .ok_or_else(|| anyhow!("missing qemu section"))?;
+                let mut hypervisor = Qemu::new();
+                hypervisor
+                    .set_hypervisor_config(hypervisor_config.clone())
+                    .await;
+                Ok(Arc::new(hypervisor) as Arc<dyn Hypervisor>)
+            })
+        });
+        let res = new_hypervisor(&toml_config).await;
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn test_init_registers_hypervisor_plugins() {
+        // init must only report success once every supported hypervisor
+        // plugin is resolvable from the registry
+        VirtContainer::init().unwrap();
+        for name in SUPPORTED_HYPERVISORS {
+            assert!(get_hypervisor_plugin(name).is_some());
+        }
+    }
+
+    #[test]
+    fn test_sandbox_logger_carries_sid() {
+        use slog::{Serializer, KV};
+
+        // collect the owned key-value pairs attached to the logger
+        struct Collect(Vec<(String, String)>);
+        impl Serializer for Collect {
+            fn emit_arguments(
+                &mut self,
+                key: slog::Key,
+                val: &std::fmt::Arguments,
+            ) -> slog::Result {
+                self.0.push((key.to_string(), val.to_string()));
+                Ok(())
+            }
+        }
+
+        let logger = sandbox_logger("test-sid-123");
+        let record_static = record_static!(slog::Level::Info, "");
+        let mut collect = Collect(vec![]);
+        logger
+            .list()
+            .serialize(
+                &slog::Record::new(&record_static, &format_args!(""), b!()),
+                &mut collect,
+            )
+            .unwrap();
+
+        let kvs: std::collections::HashMap<String, String> = collect.0.into_iter().collect();
+        assert_eq!(kvs.get("sid").unwrap(), "test-sid-123");
+        assert_eq!(kvs.get("subsystem").unwrap(), "virt-container");
+    }
+
+    #[test]
+    fn test_validate_hypervisor_name() {
+        VirtContainer::init().unwrap();
+
+        let load = |config_content: &str| {
+            TomlConfig::load(config_content)
+                .map_err(|e| anyhow!("can not load config toml: {}", e))
+                .unwrap()
+        };
+
+        // supported hypervisor with a matching section
+        let toml_config = load(
+            r#"
+[hypervisor.qemu]
+path = "/bin/echo"
+kernel = "/bin/echo"
+image = "/bin/echo"
+firmware = ""
+
+[runtime]
+hypervisor_name="qemu"
+"#,
+        );
+        assert!(validate_hypervisor_name(&toml_config).is_ok());
+
+        // unsupported hypervisor name
+        let toml_config = load(
+            r#"
+[runtime]
+hypervisor_name="firecracker"
+"#,
+        );
+        let err = validate_hypervisor_name(&toml_config).unwrap_err();
+        assert!(err.to_string().contains("unsupported hypervisor"));
+
+        // supported name without a matching hypervisor section
+        let toml_config = load(
+            r#"
+[runtime]
+hypervisor_name="qemu"
+"#,
+        );
+        let err = validate_hypervisor_name(&toml_config).unwrap_err();
+        assert!(err.to_string().contains("missing [hypervisor.qemu]"));
+    }
 }
@@ -33,6 +33,7 @@ use tokio::sync::{mpsc::Sender, Mutex, RwLock};
 use tracing::instrument;
 
 use crate::health_check::HealthCheck;
+use crate::vm_factory::FactoryBase;
 
 pub(crate) const VIRTCONTAINER: &str = "virt_container";
 
@@ -70,6 +71,7 @@ pub struct VirtSandbox {
     agent: Arc<dyn Agent>,
     hypervisor: Arc<dyn Hypervisor>,
     monitor: Arc<HealthCheck>,
+    factory: Option<Arc<dyn FactoryBase>>,
 }
 
 impl std::fmt::Debug for VirtSandbox {
@@ -88,6 +90,7 @@ impl VirtSandbox {
         agent: Arc<dyn Agent>,
         hypervisor: Arc<dyn Hypervisor>,
         resource_manager: Arc<ResourceManager>,
+        factory: Option<Arc<dyn FactoryBase>>,
     ) -> Result<Self> {
         let config = resource_manager.config().await;
         let keep_abnormal = config.runtime.keep_abnormal;
@@ -99,6 +102,7 @@ impl VirtSandbox {
             hypervisor,
             resource_manager,
             monitor: Arc::new(HealthCheck::new(true, keep_abnormal)),
+            factory,
         })
     }
 
@@ -295,6 +299,35 @@ impl VirtSandbox {
     ) -> bool {
         !prestart_hooks.is_empty() || !create_runtime_hooks.is_empty()
     }
+
+    /// Reconfigure the guest agent's log level at runtime, without
+    /// restarting the sandbox.
+    pub async fn update_agent_log_level(&self, level: &str) -> Result<()> {
+        update_agent_log_level(&self.agent, level).await
+    }
+}
+
+/// log levels the guest agent understands
+const AGENT_LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error", "critical"];
+
+/// Send an agent config update request carrying the new log level. The
+/// level is validated on the host side so a typo fails fast instead of
+/// surfacing as an obscure agent-side error.
+async fn update_agent_log_level(agent: &Arc<dyn Agent>, level: &str) -> Result<()> {
+    if !AGENT_LOG_LEVELS.contains(&level) {
+        return Err(anyhow!(
+            "invalid agent log level {}, expect one of {:?}",
+            level,
+            AGENT_LOG_LEVELS
+        ));
+    }
+    agent
+        .update_agent_config(agent::UpdateAgentConfigRequest {
+            log_level: level.to_string(),
+        })
+        .await
+        .context("update agent config")?;
+    Ok(())
 }
 
 #[async_trait]
@@ -500,6 +533,11 @@ impl Sandbox for VirtSandbox {
             .await
             .context("resource clean up")?;
 
+        if let Some(factory) = &self.factory {
+            info!(sl!(), "close vm factory");
+            factory.close_factory().await.context("close factory")?;
+        }
+
         // TODO: cleanup other sandbox resource
         Ok(())
     }
@@ -617,6 +655,209 @@ impl Persist for VirtSandbox {
             hypervisor,
             resource_manager,
             monitor: Arc::new(HealthCheck::new(true, keep_abnormal)),
+            factory: None,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex as SyncMutex;
+
+    use agent::{AgentManager, HealthService};
+
+    use super::*;
+
+    /// Mock agent recording the log levels dispatched through
+    /// update_agent_config; every other request fails.
+    #[derive(Default, Debug)]
+    struct MockAgent {
+        dispatched_levels: SyncMutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl AgentManager for MockAgent {
+        async fn start(&self, _address: &str) -> Result<()> {
+            Err(anyhow!("not supported"))
+        }
+        async fn stop(&self) {}
+        async fn agent_sock(&self) -> Result<String> {
+            Err(anyhow!("not supported"))
+        }
+        async fn agent_config(&self) -> kata_types::config::Agent {
+            kata_types::config::Agent::default()
+        }
+    }
+
+    #[async_trait]
+    impl HealthService for MockAgent {
+        async fn check(&self, _req: agent::CheckRequest) -> Result<agent::HealthCheckResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn version(&self, _req: agent::CheckRequest) -> Result<agent::VersionCheckResponse> {
+            Err(anyhow!("not supported"))
+        }
+    }
+
+    #[async_trait]
+    impl Agent for MockAgent {
+        async fn create_sandbox(&self, _req: agent::CreateSandboxRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn destroy_sandbox(&self, _req: agent::Empty) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn online_cpu_mem(&self, _req: agent::OnlineCPUMemRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn update_agent_config(
+            &self,
+            req: agent::UpdateAgentConfigRequest,
+        ) -> Result<agent::Empty> {
+            self.dispatched_levels.lock().unwrap().push(req.log_level);
+            Ok(agent::Empty::new())
+        }
+        async fn add_arp_neighbors(
+            &self,
+            _req: agent::AddArpNeighborRequest,
+        ) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn list_interfaces(&self, _req: agent::Empty) -> Result<agent::Interfaces> {
+            Err(anyhow!("not supported"))
+        }
+        async fn list_routes(&self, _req: agent::Empty) -> Result<agent::Routes> {
+            Err(anyhow!("not supported"))
+        }
+        async fn update_interface(
+            &self,
+            _req: agent::UpdateInterfaceRequest,
+        ) -> Result<agent::Interface> {
+            Err(anyhow!("not supported"))
+        }
+        async fn update_routes(&self, _req: agent::UpdateRoutesRequest) -> Result<agent::Routes> {
+            Err(anyhow!("not supported"))
+        }
+        async fn create_container(
+            &self,
+            _req: agent::CreateContainerRequest,
+        ) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn pause_container(&self, _req: agent::ContainerID) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn remove_container(
+            &self,
+            _req: agent::RemoveContainerRequest,
+        ) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn resume_container(&self, _req: agent::ContainerID) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn start_container(&self, _req: agent::ContainerID) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn stats_container(
+            &self,
+            _req: agent::ContainerID,
+        ) -> Result<agent::StatsContainerResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn update_container(
+            &self,
+            _req: agent::UpdateContainerRequest,
+        ) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn exec_process(&self, _req: agent::ExecProcessRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn signal_process(&self, _req: agent::SignalProcessRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn wait_process(
+            &self,
+            _req: agent::WaitProcessRequest,
+        ) -> Result<agent::WaitProcessResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn close_stdin(&self, _req: agent::CloseStdinRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn read_stderr(
+            &self,
+            _req: agent::ReadStreamRequest,
+        ) -> Result<agent::ReadStreamResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn read_stdout(
+            &self,
+            _req: agent::ReadStreamRequest,
+        ) -> Result<agent::ReadStreamResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn tty_win_resize(&self, _req: agent::TtyWinResizeRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn write_stdin(
+            &self,
+            _req: agent::WriteStreamRequest,
+        ) -> Result<agent::WriteStreamResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn copy_file(&self, _req: agent::CopyFileRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn get_metrics(&self, _req: agent::Empty) -> Result<agent::MetricsResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn get_oom_event(&self, _req: agent::Empty) -> Result<agent::OomEventResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn get_ip_tables(
+            &self,
+            _req: agent::GetIPTablesRequest,
+        ) -> Result<agent::GetIPTablesResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn set_ip_tables(
+            &self,
+            _req: agent::SetIPTablesRequest,
+        ) -> Result<agent::SetIPTablesResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn get_volume_stats(
+            &self,
+            _req: agent::VolumeStatsRequest,
+        ) -> Result<agent::VolumeStatsResponse> {
+            Err(anyhow!("not supported"))
+        }
+        async fn resize_volume(&self, _req: agent::ResizeVolumeRequest) -> Result<agent::Empty> {
+            Err(anyhow!("not supported"))
+        }
+        async fn get_guest_details(
+            &self,
+            _req: agent::GetGuestDetailsRequest,
+        ) -> Result<agent::GuestDetailsResponse> {
+            Err(anyhow!("not supported"))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_update_agent_log_level_dispatches_request() {
+        let mock = Arc::new(MockAgent::default());
+        let agent = mock.clone() as Arc<dyn Agent>;
+
+        update_agent_log_level(&agent, "debug").await.unwrap();
+        assert_eq!(
+            *mock.dispatched_levels.lock().unwrap(),
+            vec!["debug".to_string()]
+        );
+
+        // an unknown level is rejected before anything reaches the agent
+        assert!(update_agent_log_level(&agent, "noisy").await.is_err());
+        assert_eq!(mock.dispatched_levels.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,181 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use kata_types::config::TomlConfig;
+use tokio::sync::RwLock;
+
+use super::{BareVM, FactoryBase, FactoryStatus};
+
+const DIRECT_FACTORY_TYPE: &str = "direct";
+
+/// The direct factory creates a fresh hypervisor for every request without
+/// caching or templating. It only keeps track of the handles it produced so
+/// that they can be dropped when the factory is closed.
+#[derive(Default)]
+pub struct Direct {
+    vms: RwLock<Vec<Arc<BareVM>>>,
+}
+
+impl Direct {
+    pub fn new() -> Self {
+        Self {
+            vms: RwLock::new(vec![]),
+        }
+    }
+
+    /// Number of base VM handles still held by the factory.
+    pub async fn held_vms(&self) -> usize {
+        self.vms.read().await.len()
+    }
+
+    /// Reset a base VM so it could be booted again without re-reading the
+    /// config.
+    ///
+    /// Not implementable yet: the `Hypervisor` trait only exposes the full
+    /// `stop_vm()` teardown, which is exactly what a reset is meant to
+    /// avoid. Until the trait grows a guest-only warm reset, this refuses
+    /// the request and callers should ask the factory for a fresh VM
+    /// instead.
+    pub async fn reset_base_vm(&self, _vm: &Arc<BareVM>) -> Result<Arc<BareVM>> {
+        Err(anyhow!(
+            "resetting a base VM is not supported: the hypervisor does not expose a guest-only reset"
+        ))
+    }
+}
+
+#[async_trait]
+impl FactoryBase for Direct {
+    async fn get_base_vm(&self, toml_config: &TomlConfig) -> Result<Arc<BareVM>> {
+        let hypervisor = crate::new_hypervisor(toml_config)
+            .await
+            .context("new hypervisor")?;
+        let vm = Arc::new(BareVM::new(hypervisor));
+        self.vms.write().await.push(vm.clone());
+        Ok(vm)
+    }
+
+    async fn get_status(&self) -> Result<FactoryStatus> {
+        Ok(FactoryStatus {
+            factory_type: DIRECT_FACTORY_TYPE.to_string(),
+            vm_count: self.held_vms().await,
+            // the direct factory creates VMs on demand, it is always ready
+            ready: true,
+        })
+    }
+
+    async fn close_factory(&self) -> Result<()> {
+        self.vms.write().await.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use anyhow::anyhow;
+    use kata_types::config::TomlConfig;
+
+    use super::*;
+    use crate::VirtContainer;
+    use common::RuntimeHandler;
+
+    #[tokio::test]
+    async fn test_direct_factory_close_releases_vms() {
+        VirtContainer::init().unwrap();
+
+        let config_content = r#"
+[hypervisor.qemu]
+path = "/bin/echo"
+kernel = "/bin/echo"
+image = "/bin/echo"
+firmware = ""
+
+[runtime]
+hypervisor_name="qemu"
+"#;
+        let toml_config = TomlConfig::load(config_content)
+            .map_err(|e| anyhow!("can not load config toml: {}", e))
+            .unwrap();
+
+        let factory = Direct::new();
+        assert_eq!(factory.held_vms().await, 0);
+
+        let vm = factory.get_base_vm(&toml_config).await.unwrap();
+        assert_eq!(factory.held_vms().await, 1);
+        assert_eq!(Arc::strong_count(&vm), 2);
+
+        factory.close_factory().await.unwrap();
+        assert_eq!(factory.held_vms().await, 0);
+        // only the caller's handle is left after the factory is closed
+        assert_eq!(Arc::strong_count(&vm), 1);
+    }
+
+    #[tokio::test]
+    async fn test_direct_factory_reset_base_vm() {
+        VirtContainer::init().unwrap();
+
+        let config_content = r#"
+[hypervisor.qemu]
+path = "/bin/echo"
+kernel = "/bin/echo"
+image = "/bin/echo"
+firmware = ""
+
+[runtime]
+hypervisor_name="qemu"
+"#;
+        let toml_config = TomlConfig::load(config_content)
+            .map_err(|e| anyhow!("can not load config toml: {}", e))
+            .unwrap();
+
+        let factory = Direct::new();
+        let vm = factory.get_base_vm(&toml_config).await.unwrap();
+
+        // the hypervisor offers no guest-only reset, so the factory refuses
+        // instead of tearing the VM down under a reset name
+        assert!(factory.reset_base_vm(&vm).await.is_err());
+
+        // the handle stays held by the factory and remains usable
+        assert_eq!(factory.held_vms().await, 1);
+        let config = vm.hypervisor().hypervisor_config().await;
+        assert!(config.path.ends_with("echo"));
+    }
+
+    #[tokio::test]
+    async fn test_direct_factory_status() {
+        VirtContainer::init().unwrap();
+
+        let config_content = r#"
+[hypervisor.qemu]
+path = "/bin/echo"
+kernel = "/bin/echo"
+image = "/bin/echo"
+firmware = ""
+
+[runtime]
+hypervisor_name="qemu"
+"#;
+        let toml_config = TomlConfig::load(config_content)
+            .map_err(|e| anyhow!("can not load config toml: {}", e))
+            .unwrap();
+
+        let factory = Direct::new();
+        let status = factory.get_status().await.unwrap();
+        assert_eq!(status.factory_type, DIRECT_FACTORY_TYPE);
+        assert_eq!(status.vm_count, 0);
+        assert!(status.ready);
+
+        let _vm = factory.get_base_vm(&toml_config).await.unwrap();
+        let status = factory.get_status().await.unwrap();
+        assert_eq!(status.vm_count, 1);
+        assert!(status.ready);
+    }
+}
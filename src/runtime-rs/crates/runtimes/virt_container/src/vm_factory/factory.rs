@@ -0,0 +1,115 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Context, Result};
+use kata_types::config::TomlConfig;
+
+use super::{BareVM, FactoryBase, FactoryStatus, VMConfig};
+
+/// Thin handle around a concrete VM factory implementation, giving callers a
+/// single place for teardown without dealing with the trait object directly.
+pub struct VMFactory {
+    inner: Arc<dyn FactoryBase>,
+}
+
+impl VMFactory {
+    pub fn new(inner: Arc<dyn FactoryBase>) -> Self {
+        Self { inner }
+    }
+
+    /// The wrapped factory implementation, for handing to components that
+    /// hold the factory across the sandbox lifetime.
+    pub fn inner(&self) -> Arc<dyn FactoryBase> {
+        self.inner.clone()
+    }
+
+    /// Obtain a base VM from the wrapped factory. A cached or template VM
+    /// may have been built from an older config; when it is not compatible
+    /// with the requested one, fall back to a fresh boot instead of
+    /// handing it out.
+    pub async fn get_base_vm(&self, toml_config: &TomlConfig) -> Result<Arc<BareVM>> {
+        let vm = self.inner.get_base_vm(toml_config).await?;
+
+        let hypervisor_name = &toml_config.runtime.hypervisor_name;
+        let requested = toml_config
+            .hypervisor
+            .get(hypervisor_name)
+            .map(VMConfig::new)
+            .ok_or_else(|| anyhow!("failed to get hypervisor for {}", &hypervisor_name))?;
+        let actual = VMConfig::new(&vm.hypervisor().hypervisor_config().await);
+        if actual.is_compatible_with(&requested) {
+            return Ok(vm);
+        }
+
+        warn!(
+            sl!(),
+            "base vm from factory is not compatible with the requested config, booting fresh"
+        );
+        let hypervisor = crate::new_hypervisor(toml_config)
+            .await
+            .context("new hypervisor")?;
+        Ok(Arc::new(BareVM::new(hypervisor)))
+    }
+
+    /// Report the wrapped factory's current status.
+    pub async fn get_status(&self) -> Result<FactoryStatus> {
+        self.inner.get_status().await
+    }
+
+    /// Close the factory, releasing any base VM handles it still holds.
+    /// Must be called when sandbox construction fails after a base VM has
+    /// been taken, since nothing else owns the handle at that point.
+    pub async fn close(&self) -> Result<()> {
+        self.inner.close_factory().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::super::Direct;
+    use super::*;
+    use crate::VirtContainer;
+    use common::RuntimeHandler;
+
+    #[tokio::test]
+    async fn test_vm_factory_close_on_failed_construction() {
+        VirtContainer::init().unwrap();
+
+        let config_content = r#"
+[hypervisor.qemu]
+path = "/bin/echo"
+kernel = "/bin/echo"
+image = "/bin/echo"
+firmware = ""
+
+[runtime]
+hypervisor_name="qemu"
+"#;
+        let toml_config = TomlConfig::load(config_content)
+            .map_err(|e| anyhow!("can not load config toml: {}", e))
+            .unwrap();
+
+        let direct = Arc::new(Direct::new());
+        let factory = VMFactory::new(direct.clone());
+        let _vm = factory.get_base_vm(&toml_config).await.unwrap();
+        assert_eq!(direct.held_vms().await, 1);
+
+        // simulate the sandbox constructor failing after the base VM was
+        // taken from the factory
+        let sandbox: Result<()> = Err(anyhow!("sandbox construction failed"));
+        if sandbox.is_err() {
+            factory.close().await.unwrap();
+        }
+
+        // the base VM handle is no longer held by the factory
+        assert_eq!(direct.held_vms().await, 0);
+        assert_eq!(factory.get_status().await.unwrap().vm_count, 0);
+    }
+}
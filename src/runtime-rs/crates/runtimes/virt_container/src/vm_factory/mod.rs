@@ -0,0 +1,203 @@
+// Copyright (c) 2019-2022 Alibaba Cloud
+// Copyright (c) 2019-2022 Ant Group
+//
+// SPDX-License-Identifier: Apache-2.0
+//
+
+mod direct;
+pub use direct::Direct;
+mod factory;
+pub use factory::VMFactory;
+
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use hypervisor::Hypervisor;
+use kata_types::config::hypervisor::Hypervisor as HypervisorConfig;
+use kata_types::config::TomlConfig;
+
+/// The subset of the hypervisor config that decides whether a cached or
+/// template VM can be reused for a sandbox. Values that can be adjusted
+/// after boot, like the default memory size, are deliberately left out;
+/// the cpu topology, the memory backend and the kernel cannot change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VMConfig {
+    vcpus: i32,
+    max_vcpus: u32,
+    file_mem_backend: String,
+    enable_hugepages: bool,
+    kernel: String,
+}
+
+impl VMConfig {
+    pub fn new(config: &HypervisorConfig) -> Self {
+        Self {
+            vcpus: config.cpu_info.default_vcpus,
+            max_vcpus: config.cpu_info.default_maxvcpus,
+            file_mem_backend: config.memory_info.file_mem_backend.clone(),
+            enable_hugepages: config.memory_info.enable_hugepages,
+            // a stored hypervisor config has its paths canonicalized,
+            // do the same so both sides compare equal
+            kernel: canonicalize_path(&config.boot_info.kernel),
+        }
+    }
+
+    /// Check that a cached/template VM built from this config can serve a
+    /// sandbox requesting the other config.
+    pub fn is_compatible_with(&self, other: &VMConfig) -> bool {
+        self == other
+    }
+}
+
+/// Canonicalize a path, falling back to the path itself when it cannot be
+/// resolved.
+fn canonicalize_path(path: &str) -> String {
+    std::fs::canonicalize(path)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.to_string())
+}
+
+/// A hypervisor handle produced by a VM factory which is not yet tied to a
+/// running sandbox.
+pub struct BareVM {
+    hypervisor: Arc<dyn Hypervisor>,
+}
+
+impl BareVM {
+    pub fn new(hypervisor: Arc<dyn Hypervisor>) -> Self {
+        Self { hypervisor }
+    }
+
+    pub fn hypervisor(&self) -> Arc<dyn Hypervisor> {
+        self.hypervisor.clone()
+    }
+
+    /// Check that this base VM was built from the same hypervisor config the
+    /// sandbox is about to use. The agent connects through a socket provided
+    /// by the hypervisor, so a base VM produced from a different config would
+    /// only fail much later with an obscure agent connection error.
+    pub async fn validate(&self, toml_config: &TomlConfig) -> Result<()> {
+        let hypervisor_name = &toml_config.runtime.hypervisor_name;
+        let expected = toml_config
+            .hypervisor
+            .get(hypervisor_name)
+            .ok_or_else(|| anyhow!("failed to get hypervisor for {}", &hypervisor_name))?;
+        let actual = self.hypervisor.hypervisor_config().await;
+
+        // the stored config has its paths canonicalized, compare apples to
+        // apples by canonicalizing the expected side as well
+        let canonical = canonicalize_path;
+        if actual.path != canonical(&expected.path) {
+            return Err(anyhow!(
+                "base vm hypervisor path {:?} does not match configured path {:?}",
+                actual.path,
+                expected.path
+            ));
+        }
+        if actual.boot_info.kernel != canonical(&expected.boot_info.kernel) {
+            return Err(anyhow!(
+                "base vm kernel {:?} does not match configured kernel {:?}",
+                actual.boot_info.kernel,
+                expected.boot_info.kernel
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Health and capacity information reported by a VM factory.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct FactoryStatus {
+    /// Factory type, e.g. "direct".
+    pub factory_type: String,
+    /// Number of base VM handles currently held by the factory.
+    pub vm_count: usize,
+    /// Whether the factory can serve new base VM requests.
+    pub ready: bool,
+}
+
+/// Common interface of VM factories.
+#[async_trait]
+pub trait FactoryBase: Send + Sync {
+    /// Obtain a base VM from the factory.
+    async fn get_base_vm(&self, toml_config: &TomlConfig) -> Result<Arc<BareVM>>;
+
+    /// Report the factory's current status for health checks and tooling.
+    async fn get_status(&self) -> Result<FactoryStatus>;
+
+    /// Release all resources held by the factory, e.g. base VM handles that
+    /// have been created but not claimed by a sandbox. Invoked on sandbox
+    /// teardown.
+    async fn close_factory(&self) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::VirtContainer;
+    use common::RuntimeHandler;
+
+    fn load_qemu_config(kernel: &str) -> TomlConfig {
+        let config_content = format!(
+            r#"
+[hypervisor.qemu]
+path = "/bin/echo"
+kernel = "{}"
+image = "/bin/echo"
+firmware = ""
+
+[runtime]
+hypervisor_name="qemu"
+"#,
+            kernel
+        );
+        TomlConfig::load(&config_content)
+            .map_err(|e| anyhow!("can not load config toml: {}", e))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_vm_config_compatibility() {
+        let toml_config = load_qemu_config("/bin/echo");
+        let base = toml_config.hypervisor.get("qemu").unwrap();
+        let config = VMConfig::new(base);
+
+        // a config built from the same hypervisor section is reusable
+        assert!(config.is_compatible_with(&VMConfig::new(base)));
+
+        // a different kernel is not
+        let other_toml_config = load_qemu_config("/bin/ls");
+        let other = VMConfig::new(other_toml_config.hypervisor.get("qemu").unwrap());
+        assert!(!config.is_compatible_with(&other));
+
+        // neither is a different cpu topology
+        let mut modified = base.clone();
+        modified.cpu_info.default_vcpus += 1;
+        assert!(!config.is_compatible_with(&VMConfig::new(&modified)));
+
+        // nor another memory backend
+        let mut modified = base.clone();
+        modified.memory_info.enable_hugepages = !modified.memory_info.enable_hugepages;
+        assert!(!config.is_compatible_with(&VMConfig::new(&modified)));
+    }
+
+    #[tokio::test]
+    async fn test_bare_vm_validate() {
+        VirtContainer::init().unwrap();
+
+        let toml_config = load_qemu_config("/bin/echo");
+        let factory = Direct::new();
+        let vm = factory.get_base_vm(&toml_config).await.unwrap();
+
+        // the base vm was built from this very config
+        assert!(vm.validate(&toml_config).await.is_ok());
+
+        // a config with a different kernel must be rejected
+        let other_config = load_qemu_config("/bin/ls");
+        assert!(vm.validate(&other_config).await.is_err());
+    }
+}
@@ -25,6 +25,15 @@ use std::os::unix::io::{FromRawFd, RawFd};
 // vsock-exporter.
 const HEADER_SIZE_BYTES: u64 = std::mem::size_of::<u64>() as u64;
 
+// The agent coalesces spans into batches and prefixes each batch with a
+// magic number and the schema version of the serialised span layout, so the
+// forwarder can reject data it would otherwise mis-deserialise.
+//
+// Must match the values of the variables of the same names in the agents
+// vsock-exporter.
+const BATCH_MAGIC: u32 = 0x4b56_5345;
+const BATCH_SCHEMA_VERSION: u16 = 1;
+
 fn mk_io_err(msg: &str) -> std::io::Error {
     std::io::Error::new(std::io::ErrorKind::Other, msg.to_string())
 }
@@ -59,8 +68,13 @@ async fn handle_trace_data<'a>(
 
         info!(logger, "waiting for traces");
 
-        match reader.read_exact(&mut header) {
-            Ok(_) => debug!(logger, "read header"),
+        // A batch header and a span header can only be told apart by their
+        // leading bytes: a payload would need to be exabytes long for the
+        // first half of its length to match the magic number.
+        let mut prefix: [u8; std::mem::size_of::<u32>()] = [0; std::mem::size_of::<u32>()];
+
+        match reader.read_exact(&mut prefix) {
+            Ok(_) => (),
             Err(e) => {
                 if e.kind() == ErrorKind::UnexpectedEof {
                     info!(logger, "agent shut down");
@@ -71,6 +85,40 @@ async fn handle_trace_data<'a>(
             }
         };
 
+        if NetworkEndian::read_u32(&prefix) == BATCH_MAGIC {
+            // Start of a new batch: validate the schema version, then read
+            // the first span header.
+            let mut version: [u8; std::mem::size_of::<u16>()] = [0; std::mem::size_of::<u16>()];
+
+            reader
+                .read_exact(&mut version)
+                .with_context(|| "failed to read batch schema version")?;
+
+            let version = NetworkEndian::read_u16(&version);
+
+            if version != BATCH_SCHEMA_VERSION {
+                return Err(anyhow!(
+                    "unsupported batch schema version {} (forwarder supports version {})",
+                    version,
+                    BATCH_SCHEMA_VERSION
+                ));
+            }
+
+            debug!(logger, "read batch header");
+
+            reader
+                .read_exact(&mut header)
+                .with_context(|| "failed to read header")?;
+        } else {
+            header[..prefix.len()].copy_from_slice(&prefix);
+
+            reader
+                .read_exact(&mut header[prefix.len()..])
+                .with_context(|| "failed to read header")?;
+        }
+
+        debug!(logger, "read header");
+
         let payload_len: u64 = NetworkEndian::read_u64(&header);
 
         let mut encoded_payload = vec![0; payload_len as usize];